@@ -0,0 +1,692 @@
+use crate::app::{App, SortMode};
+use crate::export::ExportFormat;
+use crate::task::Priority;
+use crossterm::event::KeyCode;
+
+/// A logical input to the app. Key bindings and the command palette both
+/// resolve to one of these, and `App::handle_action` carries it out for the
+/// current mode, so the app can be driven without a terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    Next,
+    Previous,
+    ToggleCompleted,
+    AddTask,
+    AddSubtask,
+    DeleteTask,
+    DuplicateTask,
+    TogglePinned,
+    OpenLink,
+    CyclePriority,
+    SetDueDate,
+    ToggleTimer,
+    SetEstimate,
+    SetEffort,
+    SetRecurrence,
+    /// Bulk edits of every task the filtered list shows, each confirmed
+    /// first; `subtasks` extends them to those tasks' subtasks. Deleting
+    /// without `subtasks` keeps the subtasks as top-level tasks.
+    BatchDueDate {
+        subtasks: bool,
+    },
+    BatchComplete {
+        subtasks: bool,
+    },
+    BatchDelete {
+        subtasks: bool,
+    },
+    BatchPriority {
+        priority: Priority,
+        subtasks: bool,
+    },
+    BatchTag {
+        subtasks: bool,
+    },
+    EditNotes,
+    PickColor,
+    EditDependencies,
+    MoveTo,
+    Search,
+    ClearSearch,
+    /// Removes the filter chip at this zero-based position
+    RemoveFilter(usize),
+    ClearFilters,
+    /// Writes the tasks to a file beside the tasks file; `filtered_only`
+    /// limits it to what the list currently shows
+    Export {
+        format: ExportFormat,
+        filtered_only: bool,
+    },
+    ToggleFocus,
+    ToggleLineNumbers,
+    ToggleDetails,
+    CycleDensity,
+    ToggleSubtaskResults,
+    Collapse,
+    Expand,
+    CollapseAll,
+    ExpandAll,
+    OpenFilterPresets,
+    NextOverdue,
+    PreviousOverdue,
+    ToggleGroupCompleted,
+    ToggleHideCompleted,
+    CycleSort,
+    SetSort(SortMode),
+    ClearCompleted,
+    ShowHelp,
+    ShowStats,
+    CycleTheme,
+    /// Switches to the theme with this key
+    SetTheme(String),
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    EditTheme,
+    OpenPalette,
+    OpenLeader,
+    /// Hides the overdue summary shown at startup
+    DismissSummary,
+    /// Mouse drag of the task list, by display row
+    DragStart(usize),
+    DragTo(usize),
+    DragEnd,
+    /// Generic keys used by popups and text inputs; what they do depends on
+    /// the mode
+    Input(char),
+    /// Text pasted into the terminal, typed into the current input
+    Paste(String),
+    Backspace,
+    PageUp,
+    PageDown,
+    Submit,
+    /// Submit but keep the input open for another entry (Ctrl+Enter)
+    SubmitAndContinue,
+    Cancel,
+    Tab,
+}
+
+/// One entry in the leader-key menu: a submenu or a command to run.
+pub enum LeaderNode {
+    Group(&'static str, Vec<(char, LeaderNode)>),
+    Run(Action),
+}
+
+/// The menus shown after pressing the leader key, grouped by topic.
+pub fn leader_menu(app: &App) -> Vec<(char, LeaderNode)> {
+    let mut themes = app.theme_manager.get_available_themes();
+    themes.sort();
+    let mut theme_entries = vec![
+        ('t', LeaderNode::Run(Action::CycleTheme)),
+        ('e', LeaderNode::Run(Action::EditTheme)),
+    ];
+    theme_entries.extend(
+        themes
+            .into_iter()
+            .zip('1'..='9')
+            .map(|(theme, key)| (key, LeaderNode::Run(Action::SetTheme(theme)))),
+    );
+
+    vec![
+        ('t', LeaderNode::Group("theme", theme_entries)),
+        (
+            's',
+            LeaderNode::Group(
+                "sort",
+                vec![
+                    ('m', LeaderNode::Run(Action::SetSort(SortMode::Manual))),
+                    ('c', LeaderNode::Run(Action::SetSort(SortMode::Created))),
+                    ('d', LeaderNode::Run(Action::SetSort(SortMode::Due))),
+                    ('s', LeaderNode::Run(Action::CycleSort)),
+                ],
+            ),
+        ),
+        (
+            'v',
+            LeaderNode::Group(
+                "view",
+                vec![
+                    ('f', LeaderNode::Run(Action::ToggleFocus)),
+                    ('h', LeaderNode::Run(Action::ToggleHideCompleted)),
+                    ('g', LeaderNode::Run(Action::ToggleGroupCompleted)),
+                    ('l', LeaderNode::Run(Action::ToggleLineNumbers)),
+                    ('i', LeaderNode::Run(Action::ToggleDetails)),
+                    ('d', LeaderNode::Run(Action::CycleDensity)),
+                    ('r', LeaderNode::Run(Action::ToggleSubtaskResults)),
+                    ('z', LeaderNode::Run(Action::CollapseAll)),
+                    ('Z', LeaderNode::Run(Action::ExpandAll)),
+                    ('s', LeaderNode::Run(Action::ShowStats)),
+                ],
+            ),
+        ),
+        (
+            'e',
+            LeaderNode::Group(
+                "edit",
+                vec![
+                    ('p', LeaderNode::Run(Action::CyclePriority)),
+                    ('d', LeaderNode::Run(Action::SetDueDate)),
+                    ('n', LeaderNode::Run(Action::EditNotes)),
+                    ('c', LeaderNode::Run(Action::PickColor)),
+                    ('b', LeaderNode::Run(Action::EditDependencies)),
+                    ('m', LeaderNode::Run(Action::MoveTo)),
+                    ('y', LeaderNode::Run(Action::DuplicateTask)),
+                    ('x', LeaderNode::Run(Action::ToggleTimer)),
+                    ('E', LeaderNode::Run(Action::SetEstimate)),
+                    ('e', LeaderNode::Run(Action::SetEffort)),
+                    ('r', LeaderNode::Run(Action::SetRecurrence)),
+                ],
+            ),
+        ),
+        (
+            'z',
+            LeaderNode::Group(
+                "zoom",
+                vec![
+                    ('+', LeaderNode::Run(Action::ZoomIn)),
+                    ('-', LeaderNode::Run(Action::ZoomOut)),
+                    ('0', LeaderNode::Run(Action::ResetZoom)),
+                ],
+            ),
+        ),
+        (
+            'x',
+            LeaderNode::Group(
+                "export",
+                vec![
+                    ('m', LeaderNode::Run(export(ExportFormat::Markdown, false))),
+                    ('M', LeaderNode::Run(export(ExportFormat::Markdown, true))),
+                    ('c', LeaderNode::Run(export(ExportFormat::Csv, false))),
+                    ('C', LeaderNode::Run(export(ExportFormat::Csv, true))),
+                ],
+            ),
+        ),
+        (':', LeaderNode::Run(Action::OpenPalette)),
+    ]
+}
+
+fn export(format: ExportFormat, filtered_only: bool) -> Action {
+    Action::Export {
+        format,
+        filtered_only,
+    }
+}
+
+/// Follows the keys pressed so far through the leader menu, returning the
+/// entries available next, or `None` if the sequence matches nothing.
+pub fn leader_entries(app: &App, pending: &[char]) -> Option<Vec<(char, LeaderNode)>> {
+    let mut entries = leader_menu(app);
+    for key in pending {
+        let index = entries.iter().position(|(k, _)| k == key)?;
+        match entries.swap_remove(index).1 {
+            LeaderNode::Group(_, children) => entries = children,
+            LeaderNode::Run(_) => return None,
+        }
+    }
+    Some(entries)
+}
+
+/// The keys that reach `action` through the leader menu, such as "Space t e".
+fn leader_sequence(app: &App, action: &Action) -> Option<String> {
+    fn find(entries: &[(char, LeaderNode)], action: &Action, path: &mut Vec<char>) -> bool {
+        for (key, node) in entries {
+            path.push(*key);
+            let found = match node {
+                LeaderNode::Run(run) => run == action,
+                LeaderNode::Group(_, children) => find(children, action, path),
+            };
+            if found {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+    let mut path = Vec::new();
+    find(&leader_menu(app), action, &mut path).then(|| {
+        std::iter::once("Space".to_string())
+            .chain(path.iter().map(char::to_string))
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+}
+
+/// A Normal-mode key binding. The table in `bindings` is the only place
+/// Normal-mode keys are bound: key presses in the list are translated through
+/// it, and the help and the palette show the keys from it. The popups' input
+/// keys and the Ctrl chords are mapped in main.rs (`key_action` and
+/// `chord_action`) and are not part of the table.
+pub struct Binding {
+    /// Keys bound to the action; none for commands only reached through the
+    /// leader menu or the palette, which the help still lists
+    pub keys: &'static [KeyCode],
+    pub action: Action,
+    /// Only bound while this holds, so a key like Esc can do different
+    /// things depending on what is showing
+    pub when: Option<fn(&App) -> bool>,
+}
+
+impl Binding {
+    fn new(keys: &'static [KeyCode], action: Action) -> Self {
+        Binding {
+            keys,
+            action,
+            when: None,
+        }
+    }
+
+    fn when(mut self, condition: fn(&App) -> bool) -> Self {
+        self.when = Some(condition);
+        self
+    }
+
+    /// The action for `code`; each of the filter keys removes its own chip.
+    fn action_for(&self, code: KeyCode) -> Action {
+        match self.action {
+            Action::RemoveFilter(_) => {
+                Action::RemoveFilter(self.keys.iter().position(|&k| k == code).unwrap_or(0))
+            }
+            _ => self.action.clone(),
+        }
+    }
+
+    /// How the keys are written in the help, like "h / F1" or "1-9".
+    fn keys_label(&self) -> Option<String> {
+        match self.keys {
+            [] => None,
+            [first, .., last] if self.keys.len() > 2 => {
+                Some(format!("{}-{}", key_label(*first), key_label(*last)))
+            }
+            keys => Some(
+                keys.iter()
+                    .map(|&k| key_label(k))
+                    .collect::<Vec<_>>()
+                    .join(" / "),
+            ),
+        }
+    }
+}
+
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+const FILTER_KEYS: &[KeyCode] = &[
+    KeyCode::Char('1'),
+    KeyCode::Char('2'),
+    KeyCode::Char('3'),
+    KeyCode::Char('4'),
+    KeyCode::Char('5'),
+    KeyCode::Char('6'),
+    KeyCode::Char('7'),
+    KeyCode::Char('8'),
+    KeyCode::Char('9'),
+];
+
+/// The Normal-mode bindings, grouped under the headings the help shows them
+/// in. A key bound more than once goes to the first binding that applies.
+pub fn bindings() -> Vec<(&'static str, Vec<Binding>)> {
+    use KeyCode::Char;
+    vec![
+        (
+            "Navigation",
+            vec![
+                Binding::new(&[KeyCode::Down], Action::Next),
+                Binding::new(&[KeyCode::Up], Action::Previous),
+                Binding::new(&[KeyCode::Enter], Action::ToggleCompleted),
+                Binding::new(&[KeyCode::Left], Action::Collapse),
+                Binding::new(&[KeyCode::Right], Action::Expand),
+                Binding::new(&[Char('z')], Action::CollapseAll),
+                Binding::new(&[Char('Z')], Action::ExpandAll),
+                Binding::new(&[Char(']')], Action::NextOverdue),
+                Binding::new(&[Char('[')], Action::PreviousOverdue),
+            ],
+        ),
+        (
+            "Task Management",
+            vec![
+                Binding::new(&[Char('a')], Action::AddTask),
+                Binding::new(&[Char('s')], Action::AddSubtask),
+                Binding::new(&[Char('d')], Action::DeleteTask),
+                Binding::new(&[Char('y')], Action::DuplicateTask),
+                Binding::new(&[Char('x')], Action::ToggleTimer),
+                Binding::new(&[Char('E')], Action::SetEstimate),
+                Binding::new(&[Char('e')], Action::SetEffort),
+                Binding::new(&[Char('r')], Action::SetRecurrence),
+                Binding::new(&[Char('m')], Action::MoveTo),
+                Binding::new(&[Char('*')], Action::TogglePinned),
+                Binding::new(&[Char('p')], Action::CyclePriority),
+                Binding::new(&[Char('D')], Action::SetDueDate),
+                Binding::new(&[Char('n')], Action::EditNotes),
+                Binding::new(&[Char('c')], Action::PickColor),
+                Binding::new(&[Char('b')], Action::EditDependencies),
+                Binding::new(&[Char('O')], Action::OpenLink),
+                Binding::new(&[Char('C')], Action::ClearCompleted),
+            ],
+        ),
+        (
+            "View & Search",
+            vec![
+                Binding::new(&[Char('/')], Action::Search),
+                Binding::new(FILTER_KEYS, Action::RemoveFilter(0)),
+                Binding::new(&[KeyCode::Backspace], Action::ClearFilters),
+                Binding::new(&[KeyCode::Esc], Action::DismissSummary)
+                    .when(|app| app.due_summary.is_some()),
+                Binding::new(&[KeyCode::Esc], Action::ClearSearch)
+                    .when(|app| app.has_search_filter()),
+                Binding::new(&[Char('R')], Action::ToggleSubtaskResults),
+                Binding::new(&[Char('F')], Action::OpenFilterPresets),
+                Binding::new(&[Char('B')], Action::BatchDueDate { subtasks: false }),
+                Binding::new(&[Char('f')], Action::ToggleFocus),
+                Binding::new(&[Char('g')], Action::ToggleGroupCompleted),
+                Binding::new(&[Char('H')], Action::ToggleHideCompleted),
+                Binding::new(&[Char('o')], Action::CycleSort),
+                Binding::new(&[Char('l')], Action::ToggleLineNumbers),
+                Binding::new(&[Char('i')], Action::ToggleDetails),
+                Binding::new(&[Char('V')], Action::CycleDensity),
+                Binding::new(&[Char('+')], Action::ZoomIn),
+                Binding::new(&[Char('-')], Action::ZoomOut),
+                Binding::new(&[Char('0')], Action::ResetZoom),
+            ],
+        ),
+        (
+            "Themes & Help",
+            vec![
+                Binding::new(&[Char('t')], Action::CycleTheme),
+                Binding::new(&[], Action::EditTheme),
+                Binding::new(&[Char('S')], Action::ShowStats),
+                Binding::new(&[Char(':')], Action::OpenPalette),
+                Binding::new(&[Char(' ')], Action::OpenLeader),
+                Binding::new(&[Char('h'), KeyCode::F(1)], Action::ShowHelp),
+                Binding::new(&[Char('q')], Action::Quit),
+            ],
+        ),
+    ]
+}
+
+/// Translates a Normal-mode key press into the command it is bound to.
+pub fn normal_mode_action(app: &App, code: KeyCode) -> Option<Action> {
+    bindings()
+        .iter()
+        .flat_map(|(_, bindings)| bindings)
+        .find(|binding| {
+            binding.keys.contains(&code) && binding.when.is_none_or(|applies| applies(app))
+        })
+        .map(|binding| binding.action_for(code))
+}
+
+/// The actions the help dialog lists, grouped under headings, in the order
+/// of the binding table.
+pub fn help_sections() -> Vec<(&'static str, Vec<Action>)> {
+    bindings()
+        .into_iter()
+        .map(|(heading, bindings)| {
+            let actions = bindings.into_iter().map(|binding| binding.action).collect();
+            (heading, actions)
+        })
+        .collect()
+}
+
+impl Action {
+    pub fn label(&self) -> String {
+        match self {
+            Action::Quit => "Quit".to_string(),
+            Action::Next => "Select next task".to_string(),
+            Action::Previous => "Select previous task".to_string(),
+            Action::ToggleCompleted => "Cycle status (todo/in progress/done)".to_string(),
+            Action::AddTask => "Add task".to_string(),
+            Action::AddSubtask => "Add subtask".to_string(),
+            Action::DeleteTask => "Delete task".to_string(),
+            Action::DuplicateTask => "Duplicate task".to_string(),
+            Action::TogglePinned => "Pin/unpin task".to_string(),
+            Action::OpenLink => "Open link in task".to_string(),
+            Action::CyclePriority => "Cycle priority".to_string(),
+            Action::SetDueDate => "Set due date".to_string(),
+            Action::ToggleTimer => "Start/stop timer".to_string(),
+            Action::SetEstimate => "Set time estimate".to_string(),
+            Action::SetEffort => "Set effort (S/M/L or points)".to_string(),
+            Action::SetRecurrence => "Set recurrence (daily, weekly, 3d...)".to_string(),
+            Action::BatchDueDate { subtasks } => {
+                format!(
+                    "Set due date on all filtered tasks{}",
+                    batch_scope(*subtasks)
+                )
+            }
+            Action::BatchComplete { subtasks } => {
+                format!("Complete all filtered tasks{}", batch_scope(*subtasks))
+            }
+            Action::BatchDelete { subtasks: true } => {
+                "Delete all filtered tasks and their subtasks".to_string()
+            }
+            Action::BatchDelete { subtasks: false } => {
+                "Delete all filtered tasks (keep subtasks)".to_string()
+            }
+            Action::BatchPriority { priority, subtasks } => format!(
+                "Set priority {:?} on all filtered tasks{}",
+                priority,
+                batch_scope(*subtasks)
+            ),
+            Action::BatchTag { subtasks } => {
+                format!("Tag all filtered tasks{}", batch_scope(*subtasks))
+            }
+            Action::EditNotes => "Edit notes".to_string(),
+            Action::PickColor => "Pick task color".to_string(),
+            Action::EditDependencies => "Edit blocked-by tasks".to_string(),
+            Action::MoveTo => "Move task to position".to_string(),
+            Action::Search => "Search".to_string(),
+            Action::ClearSearch => "Clear search filter".to_string(),
+            Action::RemoveFilter(_) => "Remove the numbered filter chip".to_string(),
+            Action::ClearFilters => "Clear all filters".to_string(),
+            Action::Export {
+                format,
+                filtered_only: false,
+            } => format!("Export {}", format.label()),
+            Action::Export {
+                format,
+                filtered_only: true,
+            } => format!("Export {} (filtered view)", format.label()),
+            Action::ToggleFocus => "Toggle focus mode".to_string(),
+            Action::ToggleLineNumbers => "Toggle line numbers".to_string(),
+            Action::ToggleDetails => "Toggle detail pane".to_string(),
+            Action::CycleDensity => "Cycle list density".to_string(),
+            Action::ToggleSubtaskResults => "Toggle subtask search results".to_string(),
+            Action::Collapse => "Collapse subtasks".to_string(),
+            Action::Expand => "Expand subtasks".to_string(),
+            Action::CollapseAll => "Collapse all subtasks".to_string(),
+            Action::ExpandAll => "Expand all subtasks".to_string(),
+            Action::OpenFilterPresets => "Filter presets".to_string(),
+            Action::NextOverdue => "Jump to next overdue task".to_string(),
+            Action::PreviousOverdue => "Jump to previous overdue task".to_string(),
+            Action::ToggleGroupCompleted => "Group completed tasks".to_string(),
+            Action::ToggleHideCompleted => "Hide/show completed tasks".to_string(),
+            Action::CycleSort => "Cycle sort order".to_string(),
+            Action::SetSort(mode) => format!("Sort: {}", mode.label()),
+            Action::ClearCompleted => "Clear completed tasks".to_string(),
+            Action::ShowHelp => "Show help".to_string(),
+            Action::ShowStats => "Show stats".to_string(),
+            Action::CycleTheme => "Cycle theme".to_string(),
+            Action::SetTheme(key) => format!("Theme: {}", key),
+            Action::ZoomIn => "Zoom in".to_string(),
+            Action::ZoomOut => "Zoom out".to_string(),
+            Action::ResetZoom => "Reset zoom".to_string(),
+            Action::EditTheme => "Edit theme colors".to_string(),
+            Action::OpenPalette => "Command palette".to_string(),
+            Action::OpenLeader => "Leader menu".to_string(),
+            Action::DismissSummary => "Dismiss due summary".to_string(),
+            Action::DragStart(_) => "Start dragging task".to_string(),
+            Action::DragTo(_) => "Drag task".to_string(),
+            Action::DragEnd => "Drop task".to_string(),
+            Action::Input(c) => format!("Type '{}'", c),
+            Action::Paste(_) => "Paste".to_string(),
+            Action::Backspace => "Backspace".to_string(),
+            Action::PageUp => "Page up".to_string(),
+            Action::PageDown => "Page down".to_string(),
+            Action::Submit => "Submit".to_string(),
+            Action::SubmitAndContinue => "Submit and continue".to_string(),
+            Action::Cancel => "Cancel".to_string(),
+            Action::Tab => "Tab".to_string(),
+        }
+    }
+
+    /// The keys bound to this action in the binding table, shown next to the
+    /// label in the palette and the help.
+    pub fn key_hint(&self) -> Option<String> {
+        bindings()
+            .into_iter()
+            .flat_map(|(_, bindings)| bindings)
+            .find(|binding| match (&binding.action, self) {
+                (Action::RemoveFilter(_), Action::RemoveFilter(_)) => true,
+                (action, this) => action == this,
+            })
+            .and_then(|binding| binding.keys_label())
+    }
+
+    /// The key shown for this action in the help: its direct binding, or
+    /// else its leader sequence.
+    pub fn help_key(&self, app: &App) -> Option<String> {
+        self.key_hint().or_else(|| leader_sequence(app, self))
+    }
+
+    /// Every action offered by the command palette, including one entry per
+    /// sort mode and per available theme.
+    pub fn palette_entries(app: &App) -> Vec<Action> {
+        let mut actions = vec![
+            Action::AddTask,
+            Action::AddSubtask,
+            Action::ToggleCompleted,
+            Action::DeleteTask,
+            Action::DuplicateTask,
+            Action::TogglePinned,
+            Action::OpenLink,
+            Action::CyclePriority,
+            Action::SetDueDate,
+            Action::ToggleTimer,
+            Action::SetEstimate,
+            Action::SetEffort,
+            Action::SetRecurrence,
+            Action::EditNotes,
+            Action::PickColor,
+            Action::EditDependencies,
+            Action::MoveTo,
+            Action::Search,
+            Action::ClearSearch,
+            Action::ClearFilters,
+        ];
+        // Each bulk edit is offered with and without the subtasks
+        for subtasks in [false, true] {
+            actions.extend([
+                Action::BatchComplete { subtasks },
+                Action::BatchDelete { subtasks },
+                Action::BatchPriority {
+                    priority: Priority::High,
+                    subtasks,
+                },
+                Action::BatchPriority {
+                    priority: Priority::Medium,
+                    subtasks,
+                },
+                Action::BatchPriority {
+                    priority: Priority::Low,
+                    subtasks,
+                },
+                Action::BatchTag { subtasks },
+                Action::BatchDueDate { subtasks },
+            ]);
+        }
+        actions.extend([
+            Action::Export {
+                format: ExportFormat::Markdown,
+                filtered_only: false,
+            },
+            Action::Export {
+                format: ExportFormat::Markdown,
+                filtered_only: true,
+            },
+            Action::Export {
+                format: ExportFormat::Csv,
+                filtered_only: false,
+            },
+            Action::Export {
+                format: ExportFormat::Csv,
+                filtered_only: true,
+            },
+            Action::ToggleFocus,
+            Action::ToggleHideCompleted,
+            Action::ToggleGroupCompleted,
+            Action::ToggleLineNumbers,
+            Action::ToggleDetails,
+            Action::CycleDensity,
+            Action::ToggleSubtaskResults,
+            Action::Collapse,
+            Action::Expand,
+            Action::CollapseAll,
+            Action::ExpandAll,
+            Action::OpenFilterPresets,
+            Action::NextOverdue,
+            Action::PreviousOverdue,
+            Action::CycleSort,
+            Action::SetSort(SortMode::Manual),
+            Action::SetSort(SortMode::Created),
+            Action::SetSort(SortMode::Due),
+            Action::ClearCompleted,
+            Action::CycleTheme,
+            Action::EditTheme,
+        ]);
+        let mut themes = app.theme_manager.get_available_themes();
+        themes.sort();
+        actions.extend(themes.into_iter().map(Action::SetTheme));
+        actions.extend([
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::ResetZoom,
+            Action::ShowStats,
+            Action::ShowHelp,
+            Action::Quit,
+        ]);
+        actions
+    }
+}
+
+/// Label suffix naming whether a bulk edit covers subtasks too.
+fn batch_scope(subtasks: bool) -> &'static str {
+    if subtasks {
+        " and subtasks"
+    } else {
+        ""
+    }
+}
+
+/// Scores `text` against a fuzzy `query`: every query character must appear
+/// in order. Consecutive matches and matches at word starts score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if previous_match == Some(found.wrapping_sub(1)) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
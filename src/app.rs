@@ -1,17 +1,46 @@
-use crate::task::{load_tasks, save_tasks, Priority, Task};
-use crate::theme::ThemeManager;
+use crate::action::{fuzzy_score, leader_entries, Action, LeaderNode};
+use crate::config::{
+    load_filter_presets, load_search_history, save_filter_presets, save_search_history,
+    template_path, CascadeCompletion, DateDialect, Density, DuplicateCheck, FilterPreset, Settings,
+    UiState,
+};
+use crate::export::{export_tasks, ExportFormat};
+use crate::task::{
+    advance_due_date, due_deadline, due_sort_time, format_duration, load_tasks, load_template,
+    local_datetime, normalize_due_date, parse_duration, save_tasks, Effort, Priority, Recurrence,
+    Status, Task, TaskList,
+};
+use crate::theme::{color_hex, parse_color, SerializableColor, Theme, ThemeManager};
 use chrono::prelude::*;
-use chrono_english::{parse_date_string, Dialect};
+use chrono_english::parse_date_string;
+use ratatui::layout::Rect;
 use ratatui::widgets::ListState;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 pub enum AppMode {
     Normal,
     Insert,
     DateInput,
+    EstimateInput,
+    EffortInput,
+    RecurrenceInput,
+    TagInput,
     Search,
+    Notes,
     Confirm,
     Help,
+    Stats,
+    ColorPicker,
+    DependencyPicker,
+    PresetPicker,
+    MoveTo,
+    CommandPalette,
+    ThemeEditor,
 }
 
 pub struct ConfirmDialog {
@@ -19,135 +48,1263 @@ pub struct ConfirmDialog {
     pub action: ConfirmAction,
 }
 
+/// Identifies a task in the tree: a top-level task, or one of its subtasks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaskRef {
+    pub task_id: usize,
+    pub subtask_id: Option<usize>,
+}
+
+/// One row of the rendered task list. Selection indices in `App::state`
+/// refer to positions in `App::display_rows`.
+#[derive(Debug, Clone)]
+pub enum DisplayRow {
+    /// A top-level task; `number` is its 1-based position in the displayed
+    /// order. `context` marks a parent shown only for its matching subtasks.
+    Task {
+        task: Task,
+        number: usize,
+        context: bool,
+    },
+    /// A subtask rendered beneath its parent
+    Subtask { parent_id: usize, task: Task },
+    /// A non-selectable section divider
+    Header(String),
+    /// A non-selectable blank row added by the list density
+    Spacer,
+}
+
+impl DisplayRow {
+    pub fn is_selectable(&self) -> bool {
+        !matches!(self, DisplayRow::Header(_) | DisplayRow::Spacer)
+    }
+
+    /// ID of the top-level task this row belongs to
+    pub fn top_level_id(&self) -> Option<usize> {
+        match self {
+            DisplayRow::Task { task, .. } => Some(task.id),
+            DisplayRow::Subtask { parent_id, .. } => Some(*parent_id),
+            DisplayRow::Header(_) | DisplayRow::Spacer => None,
+        }
+    }
+
+    /// The task (or subtask) this row shows
+    pub fn task_ref(&self) -> Option<TaskRef> {
+        match self {
+            DisplayRow::Task { task, .. } => Some(TaskRef {
+                task_id: task.id,
+                subtask_id: None,
+            }),
+            DisplayRow::Subtask { parent_id, task } => Some(TaskRef {
+                task_id: *parent_id,
+                subtask_id: Some(task.id),
+            }),
+            DisplayRow::Header(_) | DisplayRow::Spacer => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Manual,
+    Created,
+    /// Soonest due first, undated tasks last
+    Due,
+}
+
+impl SortMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Manual => "manual",
+            SortMode::Created => "oldest first",
+            SortMode::Due => "due date",
+        }
+    }
+}
+
+/// Severity of a notification, which decides its toast color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+/// A top-level task being dragged with the mouse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drag {
+    pub task_id: usize,
+    /// Display row the pointer is over; the task lands where that row's
+    /// task is when released
+    pub target: usize,
+}
+
+/// One filter narrowing the task list. The active ones are shown as numbered
+/// chips above the list and can be removed one at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActiveFilter {
+    /// A committed search term; see `search_terms`
+    Search(String),
+    HideCompleted,
+    /// Hides completed tasks and every subtask of a completed parent
+    Focus,
+    /// Limits the list to one top-level task (by ID) and the subtasks of it
+    /// that match the search; the description is kept for the chip
+    Scope(usize, String),
+}
+
+impl ActiveFilter {
+    pub fn label(&self) -> String {
+        match self {
+            ActiveFilter::Search(term) if term.contains(char::is_whitespace) => {
+                format!("\"{}\"", term)
+            }
+            ActiveFilter::Search(term) => term.clone(),
+            ActiveFilter::HideCompleted => "hide completed".to_string(),
+            ActiveFilter::Focus => "focus".to_string(),
+            ActiveFilter::Scope(_, description) => format!("in '{}'", description),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfirmAction {
-    DeleteTask(usize),
+    DeleteTask(TaskRef),
+    /// Deletes a top-level task, by ID, along with its subtasks; the dialog
+    /// also offers to promote the subtasks instead
+    DeleteWithSubtasks(usize),
     DeleteAllCompleted,
+    /// Bulk edits of the tasks and subtasks the filtered list showed when
+    /// the command was chosen
+    SetDueDates(Vec<TaskRef>, String),
+    CompleteAll(Vec<TaskRef>),
+    SetPriorities(Vec<TaskRef>, Priority),
+    AddTags(Vec<TaskRef>, Vec<String>),
+    /// Deletes top-level tasks, by ID, with their subtasks unless
+    /// `keep_subtasks`, which promotes them instead
+    DeleteAll {
+        task_ids: Vec<usize>,
+        keep_subtasks: bool,
+    },
+    /// Adds a task despite it resembling an existing one
+    AddDuplicate {
+        input: String,
+        subtask: bool,
+        /// Return to the input afterwards, as when adding tasks in a row
+        keep_open: bool,
+    },
 }
 
 pub struct App {
     pub tasks: Vec<Task>,
+    /// Next top-level task ID; persisted so IDs are never reused
+    pub next_id: usize,
+    /// File the tasks are loaded from and saved to; its extension picks the format
+    pub tasks_path: PathBuf,
     pub state: ListState,
     pub mode: AppMode,
     pub input: String,
     pub date_input: String,
+    /// The date being entered applies to every filtered task, not just the selection
+    pub batch_due_date: bool,
+    /// The bulk edit being entered also covers the subtasks of filtered tasks
+    pub batch_subtasks: bool,
+    /// Tags typed for a bulk tag edit
+    pub tag_input: String,
     pub search_input: String,
+    pub notes_input: String,
+    /// Target position typed after pressing the move key
+    pub move_input: String,
+    /// Query typed into the command palette and the highlighted match
+    pub palette_input: String,
+    pub palette_index: usize,
+    /// Keys typed after the leader key while a sequence is pending, and when
+    /// the last one was pressed
+    pub leader_keys: Option<Vec<char>>,
+    pub leader_last_key: Instant,
+    /// Submitted searches, oldest first, and the one currently recalled
+    pub search_history: Vec<String>,
+    pub search_history_index: Option<usize>,
+    /// Task the search being typed is limited to, toggled with Tab
+    pub search_scope: Option<usize>,
     pub margin: u16,
+    pub density: Density,
+    /// Whether searches list matching subtasks as their own results
+    pub subtask_results: bool,
     pub adding_subtask: bool,
+    /// Keep the add popup open after each task, toggled with Tab in it
+    pub sticky_add: bool,
+    /// Highlighted tag suggestion in the add popup, once one is picked with
+    /// the arrows, and whether Esc has hidden the suggestions until the next
+    /// `#` is typed
+    pub tag_suggestion_index: Option<usize>,
+    pub tag_suggestions_dismissed: bool,
     pub theme_manager: ThemeManager,
-    pub focus_mode: bool,
+    pub settings: Settings,
+    /// Every filter applied to the list, in the order they were added
+    pub filters: Vec<ActiveFilter>,
+    /// Save the view toggles on quit so the next run opens the same way
+    pub remember_view: bool,
+    pub show_line_numbers: bool,
+    /// Shows the selected task in full in a pane beside the list
+    pub show_details: bool,
+    pub group_completed: bool,
+    pub sort_mode: SortMode,
+    pub stats_days: u32,
+    /// Highlighted entry in the color picker; 0 clears the override
+    pub color_picker_index: usize,
+    /// Theme editor: the highlighted color slot, the color being typed for
+    /// it, and the theme as it was before any unsaved edits
+    pub theme_editor_index: usize,
+    pub theme_color_input: Option<String>,
+    pub theme_backup: Option<Theme>,
+    /// Task whose dependencies are being edited, and the highlighted candidate
+    pub dependency_target: Option<usize>,
+    pub dependency_picker_index: usize,
+    /// Saved filter presets, the highlighted one in the picker, and the name
+    /// being typed when saving the current filters as a new one
+    pub filter_presets: Vec<FilterPreset>,
+    pub preset_picker_index: usize,
+    pub preset_name_input: Option<String>,
+    /// Task whose links `O` is stepping through, and the next one to open
+    pub link_cycle: Option<(TaskRef, usize)>,
     pub confirm_dialog: Option<ConfirmDialog>,
+    pub save_error: Option<String>,
+    /// The save a quit tried failed, so the next quit exits without saving
+    pub quit_save_failed: bool,
+    /// Recent notifications, oldest first, shown as toasts until they expire
+    pub messages: VecDeque<(String, Instant, Level)>,
+    pub dirty: bool,
+    pub autosave: bool,
+    /// Another instance owns the tasks file, so changes are never written
+    pub read_only: bool,
+    /// Set once the app should exit; checked by the event loop
+    pub should_quit: bool,
+    /// Size of the last drawn frame, used to bound zooming out
+    pub frame_size: Rect,
+    /// Lines the help dialog is scrolled down by; clamped when drawn
+    pub help_scroll: u16,
+    /// Where the task rows were last drawn and how far the list was scrolled,
+    /// for mapping mouse positions to rows
+    pub list_area: Rect,
+    pub list_offset: usize,
+    pub drag: Option<Drag>,
+    /// The task being timed and when its timer started; only one runs at once
+    pub timer: Option<(TaskRef, Instant)>,
+    pub estimate_input: String,
+    pub effort_input: String,
+    pub recurrence_input: String,
+    /// Overdue and due-today counts shown in the footer from startup until
+    /// dismissed with Esc
+    pub due_summary: Option<String>,
 }
 
 impl App {
-    pub fn new_with_theme(theme_manager: ThemeManager) -> App {
+    /// Number of submitted searches kept in the history
+    const SEARCH_HISTORY_LIMIT: usize = 50;
+
+    pub fn new_with_theme(
+        theme_manager: ThemeManager,
+        settings: Settings,
+        tasks_path: PathBuf,
+    ) -> App {
         let mut state = ListState::default();
-        let tasks = load_tasks("tasks.json").unwrap_or_else(|_| Vec::new());
+        // Never start empty over an unreadable file: keep a copy first, since
+        // the next save would replace it
+        let ui_state = UiState::load();
+        let mut messages = VecDeque::new();
+        let TaskList {
+            tasks,
+            next_id,
+            repairs,
+        } = load_tasks(&tasks_path, &settings).unwrap_or_else(|e| {
+            let mut backup = tasks_path.clone().into_os_string();
+            backup.push(".bak");
+            let backup = PathBuf::from(backup);
+            let message = match fs::copy(&tasks_path, &backup) {
+                Ok(_) => format!(
+                    "Could not read {} ({}); a copy was kept as {}",
+                    tasks_path.display(),
+                    e,
+                    backup.display()
+                ),
+                Err(_) => format!("Could not read {}: {}", tasks_path.display(), e),
+            };
+            messages.push_back((message, Instant::now(), Level::Error));
+            TaskList {
+                tasks: Vec::new(),
+                next_id: 1,
+                repairs: Vec::new(),
+            }
+        });
+        // Only a few fit on screen, so the rest are summarised
+        const SHOWN_REPAIRS: usize = 3;
+        for repair in repairs.iter().take(SHOWN_REPAIRS) {
+            messages.push_back((format!("Repaired: {}", repair), Instant::now(), Level::Warn));
+        }
+        if repairs.len() > SHOWN_REPAIRS {
+            let message = format!(
+                "...and {} more repair(s) to {}",
+                repairs.len() - SHOWN_REPAIRS,
+                tasks_path.display()
+            );
+            messages.push_back((message, Instant::now(), Level::Warn));
+        }
         if !tasks.is_empty() {
             state.select(Some(0));
         }
+        let mut filters = Vec::new();
+        if ui_state.focus {
+            filters.push(ActiveFilter::Focus);
+        }
+        if ui_state.hide_completed {
+            filters.push(ActiveFilter::HideCompleted);
+        }
         App {
             tasks,
+            next_id,
+            tasks_path,
             state,
             mode: AppMode::Normal,
             input: String::new(),
             date_input: String::new(),
+            batch_due_date: false,
+            batch_subtasks: false,
+            tag_input: String::new(),
             search_input: String::new(),
-            margin: 1,
+            notes_input: String::new(),
+            move_input: String::new(),
+            palette_input: String::new(),
+            palette_index: 0,
+            leader_keys: None,
+            leader_last_key: Instant::now(),
+            search_history: load_search_history(),
+            search_history_index: None,
+            search_scope: None,
+            margin: ui_state.margin,
+            density: ui_state.density,
+            subtask_results: ui_state.subtask_results,
             adding_subtask: false,
+            sticky_add: false,
+            tag_suggestion_index: None,
+            tag_suggestions_dismissed: false,
             theme_manager,
-            focus_mode: false,
+            settings,
+            filters,
+            remember_view: true,
+            show_line_numbers: ui_state.line_numbers,
+            show_details: ui_state.details,
+            group_completed: ui_state.group_completed,
+            sort_mode: ui_state.sort,
+            stats_days: 7,
+            color_picker_index: 0,
+            theme_editor_index: 0,
+            theme_color_input: None,
+            theme_backup: None,
+            dependency_target: None,
+            dependency_picker_index: 0,
+            filter_presets: load_filter_presets(),
+            preset_picker_index: 0,
+            preset_name_input: None,
+            link_cycle: None,
             confirm_dialog: None,
+            save_error: None,
+            quit_save_failed: false,
+            messages,
+            dirty: false,
+            autosave: true,
+            read_only: false,
+            should_quit: false,
+            frame_size: Rect::default(),
+            help_scroll: 0,
+            list_area: Rect::default(),
+            list_offset: 0,
+            drag: None,
+            timer: None,
+            estimate_input: String::new(),
+            effort_input: String::new(),
+            recurrence_input: String::new(),
+            due_summary: None,
         }
     }
 
     pub fn zoom_in(&mut self) {
-        self.margin = self.margin.saturating_sub(1);
+        self.set_margin(self.margin.saturating_sub(1));
     }
 
+    /// Grows the margin, but never so far that the viewport inside the
+    /// terminal becomes unusably small.
     pub fn zoom_out(&mut self) {
-        self.margin = self.margin.saturating_add(1);
+        if self.margin < max_margin(self.frame_size) {
+            self.set_margin(self.margin + 1);
+        }
     }
 
-    pub fn next(&mut self) {
-        let displayed_tasks = self.get_displayed_tasks();
-        if displayed_tasks.is_empty() {
+    pub fn reset_zoom(&mut self) {
+        self.set_margin(UiState::default().margin);
+    }
+
+    /// Steps to the next list density and remembers it for the next run.
+    pub fn cycle_density(&mut self) {
+        let selected = self.selected_task_ref();
+        self.density = self.density.next();
+        self.reselect(selected);
+        self.notify(
+            Level::Info,
+            format!("List density: {}", self.density.label()),
+        );
+        let mut ui_state = UiState::load();
+        ui_state.density = self.density;
+        if let Err(e) = ui_state.save() {
+            self.notify(Level::Error, format!("Failed to save view state: {}", e));
+        }
+    }
+
+    /// Puts the view back to its defaults for this run, ignoring what was
+    /// saved: no filters, manual order, everything expanded. The saved view
+    /// is then left alone on quit.
+    pub fn reset_view(&mut self) {
+        let defaults = UiState::default();
+        self.margin = defaults.margin;
+        self.density = defaults.density;
+        self.subtask_results = defaults.subtask_results;
+        self.filters.clear();
+        self.show_line_numbers = defaults.line_numbers;
+        self.show_details = defaults.details;
+        self.group_completed = defaults.group_completed;
+        self.sort_mode = defaults.sort;
+        for task in self.tasks.iter_mut() {
+            task.collapsed = false;
+        }
+        self.remember_view = false;
+        self.ensure_valid_selection();
+    }
+
+    /// Stores focus mode, hide-completed, grouping, sort order and the line
+    /// number and detail pane toggles for the next run. Collapsed tasks are
+    /// kept in the tasks file itself.
+    fn save_view_state(&mut self) {
+        let mut ui_state = UiState::load();
+        ui_state.focus = self.focus_mode();
+        ui_state.hide_completed = self.filters.contains(&ActiveFilter::HideCompleted);
+        ui_state.group_completed = self.group_completed;
+        ui_state.sort = self.sort_mode;
+        ui_state.line_numbers = self.show_line_numbers;
+        ui_state.details = self.show_details;
+        if let Err(e) = ui_state.save() {
+            self.notify(Level::Error, format!("Failed to save view state: {}", e));
+        }
+    }
+
+    /// Switches searches between whole-task results and listing just the
+    /// matching subtasks, and remembers the choice for the next run.
+    pub fn toggle_subtask_results(&mut self) {
+        let selected = self.selected_task_ref();
+        self.subtask_results = !self.subtask_results;
+        self.reselect(selected);
+        self.notify(
+            Level::Info,
+            if self.subtask_results {
+                "Search results: matching subtasks"
+            } else {
+                "Search results: whole tasks"
+            },
+        );
+        let mut ui_state = UiState::load();
+        ui_state.subtask_results = self.subtask_results;
+        if let Err(e) = ui_state.save() {
+            self.notify(Level::Error, format!("Failed to save view state: {}", e));
+        }
+    }
+
+    fn set_margin(&mut self, margin: u16) {
+        if margin == self.margin {
             return;
         }
-        let i = self
+        self.margin = margin;
+        let mut ui_state = UiState::load();
+        ui_state.margin = margin;
+        if let Err(e) = ui_state.save() {
+            self.notify(Level::Error, format!("Failed to save view state: {}", e));
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.step_selection(true);
+    }
+
+    pub fn previous(&mut self) {
+        self.step_selection(false);
+    }
+
+    /// Moves the selection one selectable row forward or backward, wrapping
+    /// around and skipping section headers.
+    fn step_selection(&mut self, forward: bool) {
+        self.step_to(forward, DisplayRow::is_selectable);
+    }
+
+    /// Moves the selection to the next (or previous) open task or subtask on
+    /// screen whose due date has passed, wrapping around.
+    fn jump_to_overdue(&mut self, forward: bool) {
+        let now = Local::now().naive_local();
+        let overdue = |row: &DisplayRow| match row {
+            DisplayRow::Task { task, .. } | DisplayRow::Subtask { task, .. } => {
+                !task.is_done()
+                    && task
+                        .due_date
+                        .as_deref()
+                        .and_then(due_deadline)
+                        .is_some_and(|deadline| deadline < now)
+            }
+            DisplayRow::Header(_) | DisplayRow::Spacer => false,
+        };
+        if !self.step_to(forward, overdue) {
+            self.notify(Level::Info, "No overdue tasks in the list");
+        }
+    }
+
+    /// Selects the nearest row after (or before) the selection that passes
+    /// `wanted`, wrapping around; the selected row itself is checked last.
+    /// Returns whether one was found.
+    fn step_to(&mut self, forward: bool, wanted: impl Fn(&DisplayRow) -> bool) -> bool {
+        let rows = self.display_rows();
+        if rows.is_empty() {
+            return false;
+        }
+        let len = rows.len();
+        let mut i = self
             .state
             .selected()
-            .map_or(0, |i| (i + 1) % displayed_tasks.len());
-        self.state.select(Some(i));
+            .unwrap_or(if forward { len - 1 } else { 0 });
+        for _ in 0..len {
+            i = if forward {
+                (i + 1) % len
+            } else {
+                (i + len - 1) % len
+            };
+            if wanted(&rows[i]) {
+                self.state.select(Some(i));
+                return true;
+            }
+        }
+        false
     }
 
-    pub fn previous(&mut self) {
-        let displayed_tasks = self.get_displayed_tasks();
-        if displayed_tasks.is_empty() {
-            return;
+    /// Selects the first selectable row, or nothing if the list is empty.
+    pub fn select_first(&mut self) {
+        let first = self.display_rows().iter().position(|r| r.is_selectable());
+        self.state.select(first);
+    }
+
+    /// Keeps the selection on a selectable row after the rows have changed.
+    pub fn ensure_valid_selection(&mut self) {
+        let rows = self.display_rows();
+        let Some(selected) = self.state.selected() else {
+            return self.select_first();
+        };
+        let start = selected.min(rows.len().saturating_sub(1));
+        let candidate = (start..rows.len())
+            .chain((0..start).rev())
+            .find(|&i| rows[i].is_selectable());
+        self.state.select(candidate);
+    }
+
+    /// ID of the top-level task owning the selected row.
+    pub fn selected_task_id(&self) -> Option<usize> {
+        let selected = self.state.selected()?;
+        self.display_rows().get(selected)?.top_level_id()
+    }
+
+    /// The task or subtask shown on the selected row.
+    pub fn selected_task_ref(&self) -> Option<TaskRef> {
+        let selected = self.state.selected()?;
+        self.display_rows().get(selected)?.task_ref()
+    }
+
+    pub fn task(&self, task_ref: TaskRef) -> Option<&Task> {
+        let task = self.tasks.iter().find(|t| t.id == task_ref.task_id)?;
+        match task_ref.subtask_id {
+            Some(subtask_id) => task.sub_tasks.iter().find(|t| t.id == subtask_id),
+            None => Some(task),
+        }
+    }
+
+    pub fn task_mut(&mut self, task_ref: TaskRef) -> Option<&mut Task> {
+        let task = self.tasks.iter_mut().find(|t| t.id == task_ref.task_id)?;
+        match task_ref.subtask_id {
+            Some(subtask_id) => task.sub_tasks.iter_mut().find(|t| t.id == subtask_id),
+            None => Some(task),
         }
-        let i = self.state.selected().map_or(0, |i| {
-            (i + displayed_tasks.len() - 1) % displayed_tasks.len()
-        });
-        self.state.select(Some(i));
     }
 
     pub fn toggle_completed(&mut self) {
-        if let Some(selected_index) = self.state.selected() {
-            let displayed_tasks = self.get_displayed_tasks();
-            if let Some(selected_task) = displayed_tasks.get(selected_index) {
-                // Find the task in the main tasks vector by ID
-                if let Some(main_task) = self.tasks.iter_mut().find(|t| t.id == selected_task.id) {
-                    main_task.completed = !main_task.completed;
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task(task_ref) {
+                if task.status.next() == Status::Done && self.is_blocked(task) {
+                    self.notify(
+                        Level::Warn,
+                        format!(
+                            "Completed '{}' while it is still blocked by open tasks",
+                            task.description
+                        ),
+                    );
+                }
+            }
+            let cascade = match task_ref.subtask_id {
+                None => self.settings.cascade_completion,
+                Some(_) => CascadeCompletion::Off,
+            };
+            if let Some(task) = self.task_mut(task_ref) {
+                let was_done = task.is_done();
+                task.status = task.status.next();
+                task.completed_at = task.is_done().then(Local::now);
+                // Carried over in the same step, so the parent and its
+                // subtasks never disagree in between
+                for sub_task in task.sub_tasks.iter_mut() {
+                    match cascade {
+                        CascadeCompletion::Complete | CascadeCompletion::CompleteAndReopen
+                            if !was_done && task.status == Status::Done && !sub_task.is_done() =>
+                        {
+                            sub_task.status = Status::Done;
+                            sub_task.completed_at = task.completed_at;
+                        }
+                        CascadeCompletion::CompleteAndReopen
+                            if was_done && task.status != Status::Done && sub_task.is_done() =>
+                        {
+                            sub_task.status = task.status;
+                            sub_task.completed_at = None;
+                        }
+                        _ => {}
+                    }
                 }
+                self.dirty = true;
+            }
+            self.add_next_occurrence(task_ref);
+            // In focus mode the task just completed disappears from the list
+            if self.focus_mode() {
+                self.ensure_valid_selection();
             }
         }
     }
 
     pub fn cycle_priority(&mut self) {
-        if let Some(selected_index) = self.state.selected() {
-            let displayed_tasks = self.get_displayed_tasks();
-            if let Some(selected_task) = displayed_tasks.get(selected_index) {
-                // Find the task in the main tasks vector by ID
-                if let Some(main_task) = self.tasks.iter_mut().find(|t| t.id == selected_task.id) {
-                    main_task.priority = match main_task.priority {
-                        Priority::Low => Priority::Medium,
-                        Priority::Medium => Priority::High,
-                        Priority::High => Priority::Low,
-                    };
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.priority = match task.priority {
+                    Priority::Low => Priority::Medium,
+                    Priority::Medium => Priority::High,
+                    Priority::High => Priority::Low,
+                };
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Writes the tasks to disk, recording any failure in `save_error` so the
+    /// footer can show it. Returns `true` when the save succeeded.
+    pub fn save(&mut self) -> bool {
+        if self.read_only {
+            if self.dirty {
+                self.save_error = Some("Read-only: changes can't be saved".to_string());
+            }
+            return !self.dirty;
+        }
+        match save_tasks(&self.tasks_path, &self.tasks, self.next_id) {
+            Ok(()) => {
+                self.save_error = None;
+                self.dirty = false;
+                true
+            }
+            Err(e) => {
+                self.save_error = Some(format!(
+                    "Failed to save {}: {}",
+                    self.tasks_path.display(),
+                    e
+                ));
+                false
+            }
+        }
+    }
+
+    /// Saves and asks the event loop to exit. If the save fails the app stays
+    /// open to show the error, and a second quit exits without saving.
+    pub fn quit(&mut self) {
+        self.stop_timer();
+        if self.remember_view {
+            self.save_view_state();
+        }
+        if self.save() || self.quit_save_failed {
+            self.should_quit = true;
+        } else {
+            self.quit_save_failed = true;
+        }
+    }
+
+    /// Carries out an action in the context of the current mode. This is the
+    /// only way the event loop changes the app, so tests and scripts can drive
+    /// it the same way without a terminal.
+    pub fn handle_action(&mut self, action: Action) {
+        let action = match action {
+            // Quits from any mode (q itself only quits from the list, but
+            // Ctrl+C arrives as Quit wherever it is pressed)
+            Action::Quit => return self.quit(),
+            Action::Paste(text) => return self.paste(&text),
+            // Control characters would break the single-line inputs
+            Action::Input('\t') => Action::Input(' '),
+            Action::Input(c) if c.is_control() => return,
+            action => action,
+        };
+        match self.mode {
+            AppMode::Normal if self.leader_keys.is_some() => match action {
+                Action::Input(c) => self.leader_key(c),
+                _ => self.leader_keys = None,
+            },
+            AppMode::Normal => self.handle_normal_action(action),
+            AppMode::CommandPalette => match action {
+                Action::Submit => self.run_palette_selection(),
+                Action::Cancel => self.mode = AppMode::Normal,
+                Action::Next => self.palette_next(),
+                Action::Previous => self.palette_previous(),
+                Action::Input(c) => self.palette_push(c),
+                Action::Backspace => self.palette_pop(),
+                _ => {}
+            },
+            // While tags are being suggested, the keys that pick one take
+            // precedence; everything else types as usual
+            AppMode::Insert if !self.tag_suggestions().is_empty() => match action {
+                Action::Submit if self.tag_suggestion_index.is_some() => self.complete_tag(),
+                Action::Tab => self.complete_tag(),
+                Action::Next => self.tag_suggestion_next(),
+                Action::Previous => self.tag_suggestion_previous(),
+                Action::Cancel => {
+                    self.tag_suggestion_index = None;
+                    self.tag_suggestions_dismissed = true;
+                }
+                action => self.handle_insert_action(action),
+            },
+            AppMode::Insert => self.handle_insert_action(action),
+            AppMode::MoveTo => match action {
+                Action::Submit => self.move_selected_to_position(),
+                Action::Input(c) if c.is_ascii_digit() => self.move_input.push(c),
+                Action::Backspace => {
+                    self.move_input.pop();
+                }
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::DateInput => match action {
+                Action::Submit => self.set_due_date(),
+                Action::Input(c) => self.date_input.push(c),
+                Action::Backspace => {
+                    self.date_input.pop();
+                }
+                Action::Cancel => {
+                    self.batch_due_date = false;
+                    self.mode = AppMode::Normal;
+                }
+                _ => {}
+            },
+            AppMode::TagInput => match action {
+                Action::Submit => self.confirm_batch_tags(),
+                Action::Input(c) => self.tag_input.push(c),
+                Action::Backspace => {
+                    self.tag_input.pop();
+                }
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::EstimateInput => match action {
+                Action::Submit => self.set_estimate(),
+                Action::Input(c) => self.estimate_input.push(c),
+                Action::Backspace => {
+                    self.estimate_input.pop();
+                }
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::EffortInput => match action {
+                Action::Submit => self.set_effort(),
+                Action::Input(c) => self.effort_input.push(c),
+                Action::Backspace => {
+                    self.effort_input.pop();
+                }
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::RecurrenceInput => match action {
+                Action::Submit => self.set_recurrence(),
+                Action::Input(c) => self.recurrence_input.push(c),
+                Action::Backspace => {
+                    self.recurrence_input.pop();
                 }
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::Search => match action {
+                Action::Submit => {
+                    self.commit_search();
+                    self.apply_search_input();
+                    self.mode = AppMode::Normal;
+                    self.select_first_match();
+                }
+                Action::Tab => {
+                    self.toggle_search_scope();
+                    self.ensure_valid_selection();
+                }
+                Action::Cancel => {
+                    self.search_history_index = None;
+                    self.search_scope = None;
+                    self.search_input.clear();
+                    self.mode = AppMode::Normal;
+                    self.ensure_valid_selection();
+                }
+                Action::Previous => self.recall_previous_search(),
+                Action::Next => self.recall_next_search(),
+                Action::Input(c) => {
+                    self.search_input.push(c);
+                    self.search_history_index = None;
+                    self.ensure_valid_selection();
+                }
+                Action::Backspace => {
+                    self.search_input.pop();
+                    self.search_history_index = None;
+                    self.ensure_valid_selection();
+                }
+                _ => {}
+            },
+            AppMode::Notes => match action {
+                Action::Submit => self.notes_input.push('\n'),
+                Action::Input(c) => self.notes_input.push(c),
+                Action::Backspace => {
+                    self.notes_input.pop();
+                }
+                Action::Cancel => self.save_notes(),
+                _ => {}
+            },
+            AppMode::ColorPicker => match action {
+                Action::Next => self.color_picker_next(),
+                Action::Previous => self.color_picker_previous(),
+                Action::Submit => self.apply_picked_color(),
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::ThemeEditor if self.theme_color_input.is_some() => match action {
+                Action::Input(c) => {
+                    if let Some(input) = &mut self.theme_color_input {
+                        input.push(c);
+                    }
+                }
+                Action::Backspace => {
+                    if let Some(input) = &mut self.theme_color_input {
+                        input.pop();
+                    }
+                }
+                Action::Submit => self.apply_theme_color(),
+                Action::Cancel => self.theme_color_input = None,
+                _ => {}
+            },
+            AppMode::ThemeEditor => match action {
+                Action::Next => self.theme_editor_index = (self.theme_editor_index + 1) % 16,
+                Action::Previous => self.theme_editor_index = (self.theme_editor_index + 15) % 16,
+                Action::Submit => {
+                    let color =
+                        self.theme_manager.get_current_theme().colors()[self.theme_editor_index];
+                    self.theme_color_input = Some(color_hex(color));
+                }
+                Action::Input('s') => self.save_edited_theme(),
+                Action::Cancel => self.close_theme_editor(),
+                _ => {}
+            },
+            AppMode::DependencyPicker => match action {
+                Action::Next => self.dependency_picker_next(),
+                Action::Previous => self.dependency_picker_previous(),
+                Action::Submit => self.toggle_dependency(),
+                Action::Cancel => self.close_dependency_picker(),
+                _ => {}
+            },
+            AppMode::PresetPicker if self.preset_name_input.is_some() => match action {
+                Action::Input(c) => {
+                    if let Some(input) = &mut self.preset_name_input {
+                        input.push(c);
+                    }
+                }
+                Action::Backspace => {
+                    if let Some(input) = &mut self.preset_name_input {
+                        input.pop();
+                    }
+                }
+                Action::Submit => self.save_filter_preset(),
+                Action::Cancel => self.preset_name_input = None,
+                _ => {}
+            },
+            AppMode::PresetPicker => match action {
+                Action::Next => self.preset_picker_next(),
+                Action::Previous => self.preset_picker_previous(),
+                Action::Submit => self.apply_picked_preset(),
+                Action::Input('s') => self.start_naming_preset(),
+                Action::Input('d') => self.delete_picked_preset(),
+                Action::Cancel => self.mode = AppMode::Normal,
+                _ => {}
+            },
+            AppMode::Confirm => match action {
+                Action::Input('y' | 'Y') => self.execute_confirm_action(),
+                Action::Input('n' | 'N') | Action::Cancel => self.hide_confirm_dialog(),
+                Action::Input('p' | 'P') => self.promote_confirmed_subtasks(),
+                _ => {}
+            },
+            AppMode::Stats => match action {
+                Action::Tab => self.toggle_stats_range(),
+                Action::Cancel | Action::Input('S' | 'q') => self.hide_stats(),
+                _ => {}
+            },
+            AppMode::Help => match action {
+                Action::Cancel | Action::ShowHelp | Action::Input('h' | 'q') => self.hide_help(),
+                Action::Next => self.help_scroll = self.help_scroll.saturating_add(1),
+                Action::Previous => self.help_scroll = self.help_scroll.saturating_sub(1),
+                Action::PageDown => {
+                    self.help_scroll = self.help_scroll.saturating_add(self.help_page())
+                }
+                Action::PageUp => {
+                    self.help_scroll = self.help_scroll.saturating_sub(self.help_page())
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Types pasted text into the open input. Line breaks and tabs become
+    /// single spaces, except in notes where line breaks are kept, and other
+    /// control characters are dropped. Pastes outside a text input are
+    /// ignored rather than run as key presses.
+    fn paste(&mut self, text: &str) {
+        let notes = matches!(self.mode, AppMode::Notes);
+        let typing = match self.mode {
+            AppMode::Insert
+            | AppMode::DateInput
+            | AppMode::EstimateInput
+            | AppMode::EffortInput
+            | AppMode::RecurrenceInput
+            | AppMode::TagInput
+            | AppMode::Search
+            | AppMode::Notes
+            | AppMode::MoveTo
+            | AppMode::CommandPalette => true,
+            AppMode::PresetPicker => self.preset_name_input.is_some(),
+            AppMode::ThemeEditor => self.theme_color_input.is_some(),
+            _ => false,
+        };
+        if !typing {
+            return;
+        }
+
+        let mut last_space = false;
+        for c in text.replace("\r\n", "\n").chars() {
+            let action = match c {
+                '\n' | '\r' if notes => Action::Submit,
+                c if c.is_whitespace() => {
+                    if last_space {
+                        continue;
+                    }
+                    Action::Input(' ')
+                }
+                c if c.is_control() => continue,
+                c => Action::Input(c),
+            };
+            last_space = action == Action::Input(' ');
+            self.handle_action(action);
+        }
+    }
+
+    fn handle_insert_action(&mut self, action: Action) {
+        match action {
+            Action::Submit => self.add_task(self.sticky_add),
+            Action::SubmitAndContinue => self.add_task(true),
+            Action::Tab => self.sticky_add = !self.sticky_add,
+            Action::Input(c) => {
+                self.push_input_char(c);
+                self.tag_suggestion_index = None;
+                if c == '#' {
+                    self.tag_suggestions_dismissed = false;
+                }
+            }
+            Action::Backspace => {
+                self.input.pop();
+                self.tag_suggestion_index = None;
             }
+            Action::Cancel => {
+                self.adding_subtask = false;
+                self.mode = AppMode::Normal;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_normal_action(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.quit(),
+            Action::Next => self.next(),
+            Action::Previous => self.previous(),
+            Action::ToggleCompleted => self.toggle_completed(),
+            Action::AddTask => {
+                self.adding_subtask = false;
+                self.mode = AppMode::Insert;
+            }
+            Action::AddSubtask => {
+                self.adding_subtask = true;
+                self.mode = AppMode::Insert;
+            }
+            Action::DeleteTask => self.delete_task(),
+            Action::DuplicateTask => self.duplicate_task(),
+            Action::TogglePinned => self.toggle_pinned(),
+            Action::OpenLink => self.open_link(),
+            Action::CyclePriority => self.cycle_priority(),
+            Action::SetDueDate => self.mode = AppMode::DateInput,
+            Action::ToggleTimer => self.toggle_timer(),
+            Action::SetEstimate => self.open_estimate(),
+            Action::SetEffort => self.open_effort(),
+            Action::SetRecurrence => self.open_recurrence(),
+            Action::BatchDueDate { subtasks } => self.open_batch_due_date(subtasks),
+            Action::BatchComplete { subtasks } => self.batch_complete(subtasks),
+            Action::BatchDelete { subtasks } => self.batch_delete(subtasks),
+            Action::BatchPriority { priority, subtasks } => {
+                self.batch_set_priority(priority, subtasks)
+            }
+            Action::BatchTag { subtasks } => self.open_batch_tags(subtasks),
+            Action::EditNotes => self.open_notes(),
+            Action::PickColor => self.open_color_picker(),
+            Action::OpenFilterPresets => self.open_preset_picker(),
+            Action::EditDependencies => self.open_dependency_picker(),
+            Action::MoveTo => self.open_move_to(),
+            Action::Search => {
+                self.search_scope = None;
+                self.mode = AppMode::Search;
+            }
+            Action::ClearSearch => {
+                let selected = self.selected_task_ref();
+                self.filters
+                    .retain(|filter| !matches!(filter, ActiveFilter::Search(_)));
+                self.reselect(selected);
+            }
+            Action::RemoveFilter(index) => self.remove_filter(index),
+            Action::DragStart(row) => self.start_drag(row),
+            Action::DragTo(row) => self.drag_to(row),
+            Action::DragEnd => self.end_drag(),
+            Action::Export {
+                format,
+                filtered_only,
+            } => self.export(format, filtered_only),
+            Action::ClearFilters => {
+                let selected = self.selected_task_ref();
+                self.filters.clear();
+                self.reselect(selected);
+            }
+            Action::ToggleFocus => self.toggle_focus_mode(),
+            Action::ToggleLineNumbers => self.toggle_line_numbers(),
+            Action::ToggleDetails => self.toggle_details(),
+            Action::CycleDensity => self.cycle_density(),
+            Action::ToggleSubtaskResults => self.toggle_subtask_results(),
+            Action::Collapse => self.set_collapsed(true),
+            Action::Expand => self.set_collapsed(false),
+            Action::CollapseAll => self.set_all_collapsed(true),
+            Action::ExpandAll => self.set_all_collapsed(false),
+            Action::NextOverdue => self.jump_to_overdue(true),
+            Action::PreviousOverdue => self.jump_to_overdue(false),
+            Action::ToggleGroupCompleted => self.toggle_group_completed(),
+            Action::ToggleHideCompleted => self.toggle_hide_completed(),
+            Action::CycleSort => self.cycle_sort_mode(),
+            Action::SetSort(mode) => {
+                self.sort_mode = mode;
+                self.select_first();
+            }
+            Action::ClearCompleted => self.delete_all_completed(),
+            Action::ShowHelp => self.show_help(),
+            Action::ShowStats => self.show_stats(),
+            Action::CycleTheme => self.cycle_theme(),
+            Action::EditTheme => self.open_theme_editor(),
+            Action::SetTheme(key) => {
+                if let Err(e) = self.theme_manager.set_theme(&key) {
+                    self.notify(Level::Error, e);
+                }
+            }
+            Action::ZoomIn => self.zoom_in(),
+            Action::ZoomOut => self.zoom_out(),
+            Action::ResetZoom => self.reset_zoom(),
+            Action::OpenPalette => self.open_palette(),
+            Action::OpenLeader => {
+                self.leader_keys = Some(Vec::new());
+                self.leader_last_key = Instant::now();
+            }
+            Action::DismissSummary => self.due_summary = None,
+            Action::Input(_)
+            | Action::Paste(_)
+            | Action::Backspace
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Submit
+            | Action::SubmitAndContinue
+            | Action::Cancel
+            | Action::Tab => {}
+        }
+    }
+
+    /// A pending leader sequence is abandoned after this long without input.
+    const LEADER_TIMEOUT: Duration = Duration::from_secs(3);
+
+    /// How long a toast stays on screen, and how many are kept at once.
+    const MESSAGE_DURATION: Duration = Duration::from_secs(4);
+    const MESSAGE_LIMIT: usize = 5;
+
+    /// Shows a transient message to the user; the oldest ones are dropped
+    /// once too many pile up.
+    pub fn notify(&mut self, level: Level, message: impl Into<String>) {
+        self.messages
+            .push_back((message.into(), Instant::now(), level));
+        while self.messages.len() > Self::MESSAGE_LIMIT {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Advances the pending leader sequence. Completing a sequence runs its
+    /// action; a key that matches nothing cancels the sequence.
+    pub fn leader_key(&mut self, key: char) {
+        let Some(mut keys) = self.leader_keys.take() else {
+            return;
+        };
+        let Some(entries) = leader_entries(self, &keys) else {
+            return;
+        };
+        match entries.into_iter().find(|(k, _)| *k == key) {
+            Some((_, LeaderNode::Run(action))) => self.handle_action(action),
+            Some((_, LeaderNode::Group(..))) => {
+                keys.push(key);
+                self.leader_keys = Some(keys);
+                self.leader_last_key = Instant::now();
+            }
+            None => {}
+        }
+    }
+
+    pub fn open_palette(&mut self) {
+        self.palette_input.clear();
+        self.palette_index = 0;
+        self.mode = AppMode::CommandPalette;
+    }
+
+    /// Palette actions matching the typed query, best match first.
+    pub fn palette_matches(&self) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = Action::palette_entries(self)
+            .into_iter()
+            .filter_map(|action| {
+                fuzzy_score(&self.palette_input, &action.label()).map(|score| (score, action))
+            })
+            .collect();
+        // Stable, so equally good matches keep the palette's own order
+        scored.sort_by_key(|(score, _)| -score);
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+
+    pub fn palette_push(&mut self, c: char) {
+        self.palette_input.push(c);
+        self.palette_index = 0;
+    }
+
+    pub fn palette_pop(&mut self) {
+        self.palette_input.pop();
+        self.palette_index = 0;
+    }
+
+    pub fn palette_next(&mut self) {
+        let len = self.palette_matches().len();
+        if len > 0 {
+            self.palette_index = (self.palette_index + 1) % len;
+        }
+    }
+
+    pub fn palette_previous(&mut self) {
+        let len = self.palette_matches().len();
+        if len > 0 {
+            self.palette_index = (self.palette_index + len - 1) % len;
+        }
+    }
+
+    /// Closes the palette and runs the highlighted action, if any matched.
+    pub fn run_palette_selection(&mut self) {
+        let action = self.palette_matches().into_iter().nth(self.palette_index);
+        self.mode = AppMode::Normal;
+        if let Some(action) = action {
+            self.handle_action(action);
         }
     }
 
-    pub fn save(&self) {
-        save_tasks("tasks.json", &self.tasks).unwrap_or(());
+    /// Hands out a top-level task ID that has never been used in this file.
+    fn allocate_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Called periodically from the event loop; flushes pending changes when
+    /// autosave is enabled.
+    pub fn on_tick(&mut self) {
+        if self.leader_keys.is_some() && self.leader_last_key.elapsed() >= Self::LEADER_TIMEOUT {
+            self.leader_keys = None;
+        }
+        self.messages
+            .retain(|(_, shown_at, _)| shown_at.elapsed() < Self::MESSAGE_DURATION);
+        if self.autosave && self.dirty && !self.read_only {
+            self.save();
+        }
     }
 
     fn extract_date_and_clean_description(&self, input: &str) -> (String, Option<String>) {
-        let now = Local::now();
+        self.extract_date_and_clean_description_at(input, Local::now())
+    }
 
+    /// `extract_date_and_clean_description`, with relative dates read
+    /// against `now`.
+    fn extract_date_and_clean_description_at(
+        &self,
+        input: &str,
+        now: DateTime<Local>,
+    ) -> (String, Option<String>) {
         // First try chrono-english for full natural language parsing
-        if let Ok(parsed_date) = parse_date_string(input, now, Dialect::Us) {
+        let dialect = self.settings.date_dialect.to_chrono();
+        if let Ok(parsed_date) = parse_date_string(input, now, dialect) {
             // If chrono-english parsed it successfully, trust its result
             let due_date = if parsed_date.time().hour() != 0 || parsed_date.time().minute() != 0 {
                 parsed_date.format("%Y-%m-%d %H:%M").to_string()
             } else {
                 parsed_date.format("%Y-%m-%d").to_string()
             };
-            return (input.to_string(), Some(due_date));
+
+            // chrono-english reads a leading date expression and ignores any
+            // trailing words, so the shortest run of leading words that parses
+            // to the same date is the text to strip from the description
+            let words: Vec<&str> = input.split_whitespace().collect();
+            let date_word_count = (1..=words.len())
+                .find(|&n| {
+                    parse_date_string(&words[..n].join(" "), now, dialect)
+                        .is_ok_and(|date| date == parsed_date)
+                })
+                .unwrap_or(words.len());
+            // chrono-english reads a leading "1/2" as a date too; it is left
+            // to the explicit date rules below, which want a year or a
+            // preposition first
+            let fraction = Regex::new(r"^\d{1,2}/\d{1,2}$")
+                .is_ok_and(|re| re.is_match(&words[..date_word_count].join(" ")));
+            if !fraction {
+                return (words[date_word_count..].join(" "), Some(due_date));
+            }
         }
 
         // If chrono-english fails, use our smart context-aware parsing
         let time_result = self.parse_time_with_context(input, now);
 
-        if let Some((parsed_datetime, matched_text)) = time_result {
+        if let Some((parsed_datetime, matched_fragments)) = time_result {
             // Remove the matched time/date text from description
-            let cleaned_description = input
-                .replace(&matched_text, "")
-                .trim()
-                .replace("  ", " ") // Clean up double spaces
-                .to_string();
+            let cleaned_description = strip_fragments(input, &matched_fragments);
 
             let due_date =
                 if parsed_datetime.time().hour() != 0 || parsed_datetime.time().minute() != 0 {
@@ -167,27 +1324,37 @@ impl App {
         &self,
         input: &str,
         now: DateTime<Local>,
-    ) -> Option<(DateTime<Local>, String)> {
+    ) -> Option<(DateTime<Local>, Vec<String>)> {
         let input_lower = input.to_lowercase();
 
+        // Explicit calendar dates ("2024-12-25", "dec 25", "on 25/12")
+        if let Some((date, matched_date)) = self.extract_explicit_date(input, now.date_naive()) {
+            let remainder = strip_fragments(input, std::slice::from_ref(&matched_date));
+            let mut matched = vec![matched_date];
+            let time = match self.extract_time_from_text(&remainder) {
+                Some((time, matched_time)) => {
+                    matched.push(matched_time);
+                    time
+                }
+                None => NaiveTime::MIN,
+            };
+            return Some((local_datetime(date.and_time(time)), matched));
+        }
+
         // Explicit date keywords
         if input_lower.contains("today") {
             if let Some((time, matched)) = self.extract_time_from_text(&input_lower) {
                 return Some((
-                    now.date_naive()
-                        .and_time(time)
-                        .and_local_timezone(Local)
-                        .unwrap(),
-                    matched,
+                    local_datetime(now.date_naive().and_time(time)),
+                    vec!["today".to_string(), matched],
                 ));
             }
             return Some((
-                now.date_naive()
-                    .and_hms_opt(23, 59, 0)
-                    .unwrap()
-                    .and_local_timezone(Local)
-                    .unwrap(),
-                "today".to_string(),
+                local_datetime(
+                    now.date_naive()
+                        .and_time(self.settings.default_end_of_day_time),
+                ),
+                vec!["today".to_string()],
             ));
         }
 
@@ -195,44 +1362,20 @@ impl App {
             let tomorrow = now + chrono::Duration::days(1);
             if let Some((time, matched)) = self.extract_time_from_text(&input_lower) {
                 return Some((
-                    tomorrow
-                        .date_naive()
-                        .and_time(time)
-                        .and_local_timezone(Local)
-                        .unwrap(),
-                    matched,
+                    local_datetime(tomorrow.date_naive().and_time(time)),
+                    vec!["tomorrow".to_string(), matched],
                 ));
             }
             return Some((
-                tomorrow
-                    .date_naive()
-                    .and_hms_opt(9, 0, 0)
-                    .unwrap()
-                    .and_local_timezone(Local)
-                    .unwrap(),
-                "tomorrow".to_string(),
+                local_datetime(
+                    tomorrow
+                        .date_naive()
+                        .and_time(self.settings.default_morning_time),
+                ),
+                vec!["tomorrow".to_string()],
             ));
         }
 
-        // Smart time parsing with context awareness
-        if let Some((parsed_time, matched_text)) = self.extract_time_from_text(input) {
-            let target_date = if parsed_time <= now.time() {
-                // If the time has passed today, schedule for tomorrow
-                now + chrono::Duration::days(1)
-            } else {
-                // Time hasn't passed today, schedule for today
-                now
-            };
-
-            let target_datetime = target_date
-                .date_naive()
-                .and_time(parsed_time)
-                .and_local_timezone(Local)
-                .unwrap();
-
-            return Some((target_datetime, matched_text));
-        }
-
         // Day of week parsing
         let weekdays = [
             "monday",
@@ -245,81 +1388,210 @@ impl App {
         ];
         for (i, day) in weekdays.iter().enumerate() {
             if input_lower.contains(day) {
-                let target_date = self.get_next_weekday(now, i);
-                let time = if let Some((time, _)) = self.extract_time_from_text(input) {
+                // Strip a leading "next"/"this"/"on" along with the day name
+                let day_fragment = Regex::new(&format!(r"(?i)\b(?:(?:next|this|on)\s+)?{}\b", day))
+                    .ok()
+                    .and_then(|re| re.find(input).map(|m| m.as_str().to_string()))
+                    .unwrap_or_else(|| day.to_string());
+                let following_week = day_fragment.to_lowercase().starts_with("next");
+                let target_date = self.get_next_weekday(now, i, following_week);
+                let mut matched = vec![day_fragment];
+                let time = if let Some((time, matched_time)) = self.extract_time_from_text(input) {
+                    matched.push(matched_time);
                     time
                 } else {
-                    chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap() // Default to 9 AM
+                    self.settings.default_morning_time
                 };
 
-                return Some((
-                    target_date
-                        .and_time(time)
-                        .and_local_timezone(Local)
-                        .unwrap(),
-                    day.to_string(),
-                ));
+                return Some((local_datetime(target_date.and_time(time)), matched));
             }
         }
 
+        // Smart time parsing with context awareness
+        if let Some((parsed_time, matched_text)) = self.extract_time_from_text(input) {
+            let target_date = if parsed_time <= now.time() {
+                // If the time has passed today, schedule for tomorrow
+                now + chrono::Duration::days(1)
+            } else {
+                // Time hasn't passed today, schedule for today
+                now
+            };
+
+            let target_datetime = local_datetime(target_date.date_naive().and_time(parsed_time));
+
+            return Some((target_datetime, vec![matched_text]));
+        }
+
         None
     }
 
+    /// Finds a time of day in `text`. Bare numbers are only treated as times
+    /// when they are unambiguous: either with an explicit AM/PM ("10pm",
+    /// "10:30 am") or introduced by a preposition ("at 22:30", "by 17h",
+    /// "@ 9:15"), so incidental numbers like "bug 3:14" or "5h marathon" are
+    /// left in the description.
     fn extract_time_from_text(&self, text: &str) -> Option<(chrono::NaiveTime, String)> {
-        // Pattern for times like "10 PM", "10PM", "10:30 PM", "22:30"
+        // Every pattern captures (hour, optional minute, optional am/pm)
         let time_patterns = [
-            // 12-hour format with AM/PM
-            (r"\b(\d{1,2}):(\d{2})\s*(AM|PM|am|pm)\b", true),
-            (r"\b(\d{1,2})\s*(AM|PM|am|pm)\b", false),
-            (r"at\s+(\d{1,2}):(\d{2})\s*(AM|PM|am|pm)\b", true),
-            (r"at\s+(\d{1,2})\s*(AM|PM|am|pm)\b", false),
-            // 24-hour format
-            (r"\b(\d{1,2}):(\d{2})\b", true),
-            (r"\b(\d{1,2})h\b", false),
+            // Preposition + 12-hour: "at 10 PM", "by 10:30pm", "@ 9am"
+            r"(?i)(?:\b(?:at|by)\s+|@\s*)(\d{1,2})(?::(\d{2}))?\s*(am|pm)\b",
+            // Preposition + 24-hour: "at 22:30", "by 17h"
+            r"(?i)(?:\b(?:at|by)\s+|@\s*)(\d{1,2})(?::(\d{2})|h)()\b",
+            // Bare 12-hour with AM/PM: "10pm", "10:30 am"
+            r"(?i)\b(\d{1,2})(?::(\d{2}))?\s*(am|pm)\b",
         ];
 
-        for (pattern, has_minutes) in time_patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if let Some(captures) = re.captures(text) {
-                    let matched_text = captures.get(0).unwrap().as_str().to_string();
+        for pattern in time_patterns {
+            let Ok(re) = Regex::new(pattern) else {
+                continue;
+            };
+            for captures in re.captures_iter(text) {
+                let matched_text = captures[0].trim().to_string();
+                let Ok(hour) = captures[1].parse::<u32>() else {
+                    continue;
+                };
+                let minute: u32 = captures
+                    .get(2)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .unwrap_or(0);
+                let meridiem = captures
+                    .get(3)
+                    .map(|m| m.as_str().to_lowercase())
+                    .filter(|m| !m.is_empty());
 
-                    let hour: u32 = captures.get(1).unwrap().as_str().parse().ok()?;
-                    let minute: u32 = if has_minutes {
-                        captures.get(2).unwrap().as_str().parse().ok()?
-                    } else {
-                        0
-                    };
+                // Convert to 24-hour format
+                let hour_24 = match meridiem.as_deref() {
+                    Some(_) if hour == 0 || hour > 12 => continue,
+                    Some("pm") if hour != 12 => hour + 12,
+                    Some("am") if hour == 12 => 0,
+                    _ => hour,
+                };
 
-                    let is_pm = if has_minutes {
-                        captures
-                            .get(3)
-                            .is_some_and(|m| m.as_str().to_lowercase().contains('p'))
-                    } else {
-                        captures
-                            .get(2)
-                            .is_some_and(|m| m.as_str().to_lowercase().contains('p'))
-                    };
+                if let Some(time) = chrono::NaiveTime::from_hms_opt(hour_24, minute, 0) {
+                    return Some((time, matched_text));
+                }
+            }
+        }
 
-                    // Convert to 24-hour format
-                    let hour_24 = if is_pm && hour != 12 {
-                        hour + 12
-                    } else if !is_pm && hour == 12 {
-                        0
-                    } else {
-                        hour
-                    };
+        None
+    }
 
-                    if let Some(time) = chrono::NaiveTime::from_hms_opt(hour_24, minute, 0) {
-                        return Some((time, matched_text));
-                    }
-                }
+    /// Finds an explicit calendar date in `text`: ISO ("2024-12-25"), month
+    /// names ("dec 25", "25 December 2025") or numeric ("12/25/24", ordered
+    /// by the configured dialect). Dates without a year resolve to the next
+    /// occurrence on or after `today`. A preposition before the date ("on",
+    /// "by", "due", ...) is matched with it, so it is stripped too.
+    ///
+    /// A bare numeric date without a year reads just like a fraction, so
+    /// "1/2 cup flour" keeps its "1/2": one only counts after a preposition,
+    /// as in "pay rent on 1/6".
+    fn extract_explicit_date(&self, text: &str, today: NaiveDate) -> Option<(NaiveDate, String)> {
+        const MONTHS: &str = "january|jan|february|feb|march|mar|april|apr|may|june|jun|july|jul|august|aug|september|sept|sep|october|oct|november|nov|december|dec";
+        const PREPOSITION: &str = r"(?:\b(on|by|due|until|before)\s+)?";
+
+        let month_number = |name: &str| -> Option<u32> {
+            let prefix = name.get(..3)?.to_lowercase();
+            [
+                "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+            ]
+            .iter()
+            .position(|m| *m == prefix)
+            .map(|i| i as u32 + 1)
+        };
+        let parse_year = |year: Option<regex::Match>| -> Option<i32> {
+            let year: i32 = year?.as_str().parse().ok()?;
+            Some(if year < 100 { 2000 + year } else { year })
+        };
+        // Without a year, pick this year's date unless it has already passed
+        let resolve = |year: Option<i32>, month: u32, day: u32| -> Option<NaiveDate> {
+            match year {
+                Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+                None => NaiveDate::from_ymd_opt(today.year(), month, day)
+                    .filter(|date| *date >= today)
+                    .or_else(|| NaiveDate::from_ymd_opt(today.year() + 1, month, day)),
+            }
+        };
+
+        // ISO: 2024-12-25
+        let iso = format!(r"(?i){}\b(\d{{4}})-(\d{{1,2}})-(\d{{1,2}})\b", PREPOSITION);
+        if let Some(caps) = Regex::new(&iso).ok()?.captures(text) {
+            let date = NaiveDate::from_ymd_opt(
+                caps[2].parse().ok()?,
+                caps[3].parse().ok()?,
+                caps[4].parse().ok()?,
+            );
+            if let Some(date) = date {
+                return Some((date, caps[0].to_string()));
+            }
+        }
+
+        // Month first: dec 25, December 25th, 2025
+        let month_day = format!(
+            r"(?i){}\b({})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?(?:,?\s+(\d{{4}}))?\b",
+            PREPOSITION, MONTHS
+        );
+        if let Some(caps) = Regex::new(&month_day).ok()?.captures(text) {
+            let month = month_number(&caps[2])?;
+            if let Some(date) = resolve(parse_year(caps.get(4)), month, caps[3].parse().ok()?) {
+                return Some((date, caps[0].to_string()));
+            }
+        }
+
+        // Day first: 25 dec, 25th of December 2025
+        let day_month = format!(
+            r"(?i){}\b(\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?({})\b\.?(?:,?\s+(\d{{4}}))?",
+            PREPOSITION, MONTHS
+        );
+        if let Some(caps) = Regex::new(&day_month).ok()?.captures(text) {
+            let month = month_number(&caps[3])?;
+            if let Some(date) = resolve(parse_year(caps.get(4)), month, caps[2].parse().ok()?) {
+                return Some((date, caps[0].trim_end().to_string()));
+            }
+        }
+
+        // Numeric: on 25/12 or 12/25/2024 depending on dialect
+        let numeric = format!(
+            r"(?i){}\b(\d{{1,2}})/(\d{{1,2}})(?:/(\d{{4}}|\d{{2}}))?\b",
+            PREPOSITION
+        );
+        for caps in Regex::new(&numeric).ok()?.captures_iter(text) {
+            let whole = caps.get(0)?;
+            // Part of a longer run like "1/2/3/4", or a fraction
+            let joined =
+                text[..whole.start()].ends_with('/') || text[whole.end()..].starts_with('/');
+            if joined || (caps.get(1).is_none() && caps.get(4).is_none()) {
+                continue;
+            }
+            let first: u32 = caps[2].parse().ok()?;
+            let second: u32 = caps[3].parse().ok()?;
+            let (month, day) = match self.settings.date_dialect {
+                DateDialect::Us => (first, second),
+                DateDialect::Uk => (second, first),
+            };
+            if let Some(date) = resolve(parse_year(caps.get(4)), month, day) {
+                return Some((date, whole.as_str().to_string()));
             }
         }
 
         None
     }
 
-    fn get_next_weekday(&self, now: DateTime<Local>, target_weekday: usize) -> chrono::NaiveDate {
+    /// The first `target_weekday` (0 is Monday) after today or, with
+    /// `following_week` ("next friday"), that day in the week after this one,
+    /// where weeks begin on the `week_start` setting.
+    fn get_next_weekday(
+        &self,
+        now: DateTime<Local>,
+        target_weekday: usize,
+        following_week: bool,
+    ) -> chrono::NaiveDate {
+        if following_week {
+            let next_week = self.settings.week_start.start_of_week(now.date_naive())
+                + chrono::Duration::days(7);
+            let first_weekday = next_week.weekday().num_days_from_monday() as usize;
+            let offset = (target_weekday + 7 - first_weekday) % 7;
+            return next_week + chrono::Duration::days(offset as i64);
+        }
         let current_weekday = now.weekday().num_days_from_monday() as usize;
         let days_until_target = if target_weekday > current_weekday {
             target_weekday - current_weekday
@@ -330,20 +1602,200 @@ impl App {
         (now + chrono::Duration::days(days_until_target as i64)).date_naive()
     }
 
-    pub fn add_task(&mut self) {
+    /// Every tag used on a task or subtask, most used first, each spelled the
+    /// way it was first seen.
+    pub fn collect_all_tags(&self) -> Vec<String> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let all_tasks = self
+            .tasks
+            .iter()
+            .flat_map(|task| std::iter::once(task).chain(task.sub_tasks.iter()));
+        for tag in all_tasks.flat_map(|task| task.tags.iter()) {
+            match counts
+                .iter_mut()
+                .find(|(seen, _)| seen.eq_ignore_ascii_case(tag))
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((tag.clone(), 1)),
+            }
+        }
+        // Stable, so equally used tags keep their first-seen order
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts.into_iter().map(|(tag, _)| tag).collect()
+    }
+
+    /// The `#` word being typed at the end of the add input, if any.
+    fn tag_token(&self) -> Option<&str> {
+        let token = self.input.rsplit(char::is_whitespace).next()?;
+        token.starts_with('#').then_some(token)
+    }
+
+    /// Existing tags that complete the one being typed in the add popup,
+    /// empty when nothing is being typed or the suggestions were dismissed.
+    pub fn tag_suggestions(&self) -> Vec<String> {
+        const LIMIT: usize = 5;
+        let Some(token) = self.tag_token().filter(|_| !self.tag_suggestions_dismissed) else {
+            return Vec::new();
+        };
+        let typed = token.to_lowercase();
+        self.collect_all_tags()
+            .into_iter()
+            .filter(|tag| {
+                let tag = tag.to_lowercase();
+                tag.starts_with(&typed) && tag != typed
+            })
+            .take(LIMIT)
+            .collect()
+    }
+
+    pub fn tag_suggestion_next(&mut self) {
+        let len = self.tag_suggestions().len();
+        self.tag_suggestion_index = Some(self.tag_suggestion_index.map_or(0, |i| (i + 1) % len));
+    }
+
+    pub fn tag_suggestion_previous(&mut self) {
+        let len = self.tag_suggestions().len();
+        self.tag_suggestion_index = Some(
+            self.tag_suggestion_index
+                .map_or(len - 1, |i| (i + len - 1) % len),
+        );
+    }
+
+    /// Replaces the tag being typed with the highlighted suggestion (the
+    /// first, if none is highlighted), ready for the next word.
+    pub fn complete_tag(&mut self) {
+        let index = self.tag_suggestion_index.take().unwrap_or(0);
+        let Some(tag) = self.tag_suggestions().into_iter().nth(index) else {
+            return;
+        };
+        let typed = self.tag_token().map_or(0, str::len);
+        self.input.truncate(self.input.len() - typed);
+        self.input.push_str(&tag);
+        self.input.push(' ');
+    }
+
+    /// Appends to the task input unless that would exceed the configured
+    /// maximum length, counted in characters rather than bytes.
+    pub fn push_input_char(&mut self, c: char) {
+        if let Some(max) = self.settings.max_input_length {
+            if self.input.chars().count() >= max {
+                return;
+            }
+        }
+        self.input.push(c);
+    }
+
+    /// Adds the typed task, unless it looks like an open task that already
+    /// exists, in which case the user is asked first. With `keep_open` the
+    /// popup stays up with an empty input for the next one (of the same
+    /// kind, task or subtask). Input with nothing to describe the task is
+    /// refused and the popup stays open.
+    pub fn add_task(&mut self, keep_open: bool) {
+        if !has_description(&self.input) {
+            self.notify(Level::Warn, "Type a description for the task");
+            return;
+        }
+        if let Some(existing) = self.similar_open_task(&self.input) {
+            let message = format!("Similar task exists: '{}'. Add anyway?", existing);
+            let input = std::mem::take(&mut self.input);
+            let subtask = std::mem::take(&mut self.adding_subtask);
+            self.show_confirm_dialog(
+                message,
+                ConfirmAction::AddDuplicate {
+                    input,
+                    subtask,
+                    keep_open,
+                },
+            );
+            return;
+        }
+        let subtask = self.adding_subtask;
+        self.insert_task();
+        if keep_open {
+            self.adding_subtask = subtask;
+            self.mode = AppMode::Insert;
+        }
+    }
+
+    /// Description of an open task, among the ones the new task would sit
+    /// beside, that the input duplicates under the configured check.
+    fn similar_open_task(&self, input: &str) -> Option<String> {
+        let check = self.settings.duplicate_check;
+        if check == DuplicateCheck::Off {
+            return None;
+        }
+        let siblings: &[Task] = if self.adding_subtask {
+            let parent_id = self.selected_task_id()?;
+            &self.tasks.iter().find(|t| t.id == parent_id)?.sub_tasks
+        } else {
+            &self.tasks
+        };
+        let new_words = self.normalized_words(input);
+        if new_words.is_empty() {
+            return None;
+        }
+        siblings
+            .iter()
+            .filter(|task| !task.is_done())
+            .find(|task| {
+                let words = self.normalized_words(&task.description);
+                words == new_words
+                    || (check == DuplicateCheck::Fuzzy && words_similar(&words, &new_words))
+            })
+            .map(|task| task.description.clone())
+    }
+
+    /// Adds the configured default tags that weren't typed (ignoring case)
+    /// after the typed ones. They are only stored as tags, never added to the
+    /// description.
+    fn with_default_tags(&self, mut tags: Vec<String>) -> Vec<String> {
+        for tag in self.settings.default_tags_for(&self.tasks_path) {
+            if !tags
+                .iter()
+                .any(|typed| typed.to_lowercase() == tag.to_lowercase())
+            {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Lowercased words of a description with tags and any date phrase left
+    /// out, for duplicate comparison.
+    fn normalized_words(&self, text: &str) -> Vec<String> {
+        let (cleaned, _) = self.extract_date_and_clean_description(text);
+        strip_tags(&cleaned)
+            .split_whitespace()
+            .map(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase()
+            })
+            .filter(|word| !word.is_empty())
+            .collect()
+    }
+
+    /// The description (without tags) and due date for a new task. Input
+    /// that is nothing but a date phrase, like "May" or "Monday", names the
+    /// task rather than dating it, since nothing would be left to describe.
+    fn task_text_and_due(&self, input: &str) -> (String, Option<String>) {
+        let (cleaned, due_date) = self.extract_date_and_clean_description(input);
+        let cleaned = strip_tags(&cleaned);
+        if cleaned.trim().is_empty() {
+            return (strip_tags(input), None);
+        }
+        (cleaned, due_date)
+    }
+
+    fn insert_task(&mut self) {
         if self.adding_subtask {
             self.add_sub_task();
             self.adding_subtask = false;
         } else {
-            let new_id = self.tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
-            let (cleaned_description, due_date) =
-                self.extract_date_and_clean_description(&self.input);
-            let tags = self
-                .input
-                .split_whitespace()
-                .filter(|word| word.starts_with('#'))
-                .map(|word| word.to_string())
-                .collect();
+            let new_id = self.allocate_id();
+            let (cleaned_description, due_date) = self.task_text_and_due(&self.input);
+            let priority = infer_priority(&self.input);
+            let due_date = due_date.or_else(|| self.default_due_date(&priority));
+            let tags = self.with_default_tags(extract_tags(&self.input));
 
             let new_task = Task {
                 id: new_id,
@@ -352,236 +1804,1902 @@ impl App {
                 } else {
                     cleaned_description
                 },
-                completed: false,
-                priority: Priority::Medium,
+                status: Status::Todo,
+                priority,
                 due_date,
                 sub_tasks: Vec::new(),
                 tags,
+                notes: None,
+                created_at: Local::now(),
+                completed_at: None,
+                color: None,
+                pinned: false,
+                blocked_by: Vec::new(),
+                estimate: None,
+                effort: None,
+                recurrence: None,
+                time_spent: Duration::ZERO,
+                collapsed: false,
             };
             self.tasks.push(new_task);
+            self.dirty = true;
         }
         self.input.clear();
         self.mode = AppMode::Normal;
     }
 
+    /// The configured default deadline for a new task of this priority,
+    /// used only when its input named no date. It is applied once at
+    /// creation, so later priority changes leave the due date alone.
+    fn default_due_date(&self, priority: &Priority) -> Option<String> {
+        let phrase = self.settings.default_due.for_priority(priority)?;
+        self.extract_date_and_clean_description(phrase).1
+    }
+
     pub fn add_sub_task(&mut self) {
-        if let Some(selected_index) = self.state.selected() {
-            let displayed_tasks = self.get_displayed_tasks();
-            if let Some(selected_task) = displayed_tasks.get(selected_index) {
-                let selected_task_id = selected_task.id;
-
-                // Extract data before getting mutable reference
-                let (cleaned_description, due_date) =
-                    self.extract_date_and_clean_description(&self.input);
-                let tags = self
-                    .input
-                    .split_whitespace()
-                    .filter(|word| word.starts_with('#'))
-                    .map(|word| word.to_string())
-                    .collect();
+        if let Some(selected_task_id) = self.selected_task_id() {
+            // Extract data before getting mutable reference
+            let (cleaned_description, due_date) = self.task_text_and_due(&self.input);
+            let priority = infer_priority(&self.input);
+            let due_date = due_date.or_else(|| self.default_due_date(&priority));
+            let tags = self.with_default_tags(extract_tags(&self.input));
 
-                // Find the task in the main tasks vector by ID
-                if let Some(main_task) = self.tasks.iter_mut().find(|t| t.id == selected_task_id) {
-                    let new_id = main_task.sub_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+            // Find the task in the main tasks vector by ID
+            if let Some(main_task) = self.tasks.iter_mut().find(|t| t.id == selected_task_id) {
+                let new_id = main_task.sub_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
 
-                    let new_task = Task {
-                        id: new_id,
-                        description: if cleaned_description.trim().is_empty() {
-                            self.input.clone()
-                        } else {
-                            cleaned_description
-                        },
-                        completed: false,
-                        priority: Priority::Medium,
-                        due_date,
-                        sub_tasks: Vec::new(),
-                        tags,
-                    };
-                    main_task.sub_tasks.push(new_task);
-                }
+                let new_task = Task {
+                    id: new_id,
+                    description: if cleaned_description.trim().is_empty() {
+                        self.input.clone()
+                    } else {
+                        cleaned_description
+                    },
+                    status: Status::Todo,
+                    priority,
+                    due_date,
+                    sub_tasks: Vec::new(),
+                    tags,
+                    notes: None,
+                    created_at: Local::now(),
+                    completed_at: None,
+                    color: None,
+                    pinned: false,
+                    blocked_by: Vec::new(),
+                    estimate: None,
+                    effort: None,
+                    recurrence: None,
+                    time_spent: Duration::ZERO,
+                    collapsed: false,
+                };
+                main_task.sub_tasks.push(new_task);
+                self.dirty = true;
             }
         }
         self.input.clear();
         self.mode = AppMode::Normal;
     }
 
-    pub fn set_due_date(&mut self) {
-        if let Some(selected_index) = self.state.selected() {
-            let displayed_tasks = self.get_displayed_tasks();
-            if let Some(selected_task) = displayed_tasks.get(selected_index) {
-                // Find the task in the main tasks vector by ID
-                if let Some(main_task) = self.tasks.iter_mut().find(|t| t.id == selected_task.id) {
-                    main_task.due_date = Some(self.date_input.drain(..).collect());
-                }
+    /// The tasks a bulk edit applies to: every top-level task the list shows
+    /// and, with `subtasks`, all of their subtasks. They are resolved to refs
+    /// before anything changes, so the edit covers exactly what was on screen.
+    /// Refuses, with a warning, while no filter narrows the list.
+    fn batch_targets(&mut self, subtasks: bool) -> Option<Vec<TaskRef>> {
+        if self.filters.is_empty() {
+            self.notify(
+                Level::Warn,
+                "Filter the list (/ or f) first to choose the tasks to update",
+            );
+            return None;
+        }
+        let mut targets = Vec::new();
+        for task in self.get_displayed_tasks() {
+            targets.push(TaskRef {
+                task_id: task.id,
+                subtask_id: None,
+            });
+            if subtasks {
+                targets.extend(task.sub_tasks.iter().map(|sub_task| TaskRef {
+                    task_id: task.id,
+                    subtask_id: Some(sub_task.id),
+                }));
             }
         }
+        if targets.is_empty() {
+            self.notify(Level::Warn, "No tasks match the current filters");
+            return None;
+        }
+        Some(targets)
+    }
+
+    /// Counts targets for a confirmation, e.g. "3 task(s) and 4 subtask(s)".
+    fn describe_targets(targets: &[TaskRef]) -> String {
+        let subtasks = targets.iter().filter(|t| t.subtask_id.is_some()).count();
+        let tasks = targets.len() - subtasks;
+        if subtasks == 0 {
+            format!("{} task(s)", tasks)
+        } else {
+            format!("{} task(s) and {} subtask(s)", tasks, subtasks)
+        }
+    }
+
+    /// Starts entering a due date for every task matching the active filter.
+    pub fn open_batch_due_date(&mut self, subtasks: bool) {
+        if self.batch_targets(subtasks).is_none() {
+            return;
+        }
+        self.batch_due_date = true;
+        self.batch_subtasks = subtasks;
+        self.mode = AppMode::DateInput;
+    }
+
+    pub fn batch_complete(&mut self, subtasks: bool) {
+        let Some(targets) = self.batch_targets(subtasks) else {
+            return;
+        };
+        let message = format!("Complete {}?", Self::describe_targets(&targets));
+        self.show_confirm_dialog(message, ConfirmAction::CompleteAll(targets));
+    }
+
+    pub fn batch_delete(&mut self, subtasks: bool) {
+        let Some(targets) = self.batch_targets(false) else {
+            return;
+        };
+        let task_ids: Vec<usize> = targets.iter().map(|t| t.task_id).collect();
+        let subtask_count: usize = task_ids
+            .iter()
+            .filter_map(|id| self.tasks.iter().find(|t| t.id == *id))
+            .map(|task| task.sub_tasks.len())
+            .sum();
+        let message = match (subtasks, subtask_count) {
+            (_, 0) => format!("Delete {} task(s)?", task_ids.len()),
+            (true, _) => format!(
+                "Delete {} task(s) and their {} subtask(s)?",
+                task_ids.len(),
+                subtask_count
+            ),
+            (false, _) => format!(
+                "Delete {} task(s), keeping their {} subtask(s) as tasks?",
+                task_ids.len(),
+                subtask_count
+            ),
+        };
+        self.show_confirm_dialog(
+            message,
+            ConfirmAction::DeleteAll {
+                task_ids,
+                keep_subtasks: !subtasks,
+            },
+        );
+    }
+
+    pub fn batch_set_priority(&mut self, priority: Priority, subtasks: bool) {
+        let Some(targets) = self.batch_targets(subtasks) else {
+            return;
+        };
+        let message = format!(
+            "Set priority {:?} on {}?",
+            priority,
+            Self::describe_targets(&targets)
+        );
+        self.show_confirm_dialog(message, ConfirmAction::SetPriorities(targets, priority));
+    }
+
+    pub fn open_batch_tags(&mut self, subtasks: bool) {
+        if self.batch_targets(subtasks).is_none() {
+            return;
+        }
+        self.batch_subtasks = subtasks;
+        self.tag_input.clear();
+        self.mode = AppMode::TagInput;
+    }
+
+    /// Reads the typed tags, with or without their leading '#', and asks
+    /// before adding them to every filtered task.
+    fn confirm_batch_tags(&mut self) {
         self.mode = AppMode::Normal;
+        let input = std::mem::take(&mut self.tag_input);
+        let mut tags: Vec<String> = Vec::new();
+        for word in input.split([' ', ',']).filter(|word| !word.is_empty()) {
+            let tag = format!("#{}", word.trim_start_matches('#'));
+            if tag.len() > 1 && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        if tags.is_empty() {
+            return;
+        }
+        let Some(targets) = self.batch_targets(self.batch_subtasks) else {
+            return;
+        };
+        let message = format!(
+            "Add {} to {}?",
+            tags.join(" "),
+            Self::describe_targets(&targets)
+        );
+        self.show_confirm_dialog(message, ConfirmAction::AddTags(targets, tags));
     }
 
-    pub fn delete_task(&mut self) {
-        if let Some(selected_index) = self.state.selected() {
-            let displayed_tasks = self.get_displayed_tasks();
-            if let Some(selected_task) = displayed_tasks.get(selected_index) {
-                // Find the task index in the main tasks vector
-                if let Some(main_index) = self.tasks.iter().position(|t| t.id == selected_task.id) {
-                    let message = format!("Delete task: '{}'?", selected_task.description);
-                    self.show_confirm_dialog(message, ConfirmAction::DeleteTask(main_index));
-                }
+    /// Reads the typed date the way dates in new tasks are read, falling
+    /// back to the other shapes accepted in the tasks file, and stores it in
+    /// the standard form. An empty date clears the selected task's.
+    pub fn set_due_date(&mut self) {
+        let batch = std::mem::take(&mut self.batch_due_date);
+        let input: String = self.date_input.drain(..).collect();
+        self.mode = AppMode::Normal;
+        if input.trim().is_empty() {
+            if let Some(task) = self
+                .selected_task_ref()
+                .filter(|_| !batch)
+                .and_then(|task_ref| self.task_mut(task_ref))
+            {
+                task.due_date = None;
+                self.dirty = true;
+            }
+            return;
+        }
+        let Some(due_date) = self
+            .extract_date_and_clean_description(&input)
+            .1
+            .or_else(|| normalize_due_date(&input, Local::now(), self.settings.date_dialect))
+        else {
+            self.notify(
+                Level::Warn,
+                format!("Couldn't read '{}' as a date", input.trim()),
+            );
+            return;
+        };
+        if batch {
+            // Resolve to IDs now so the confirmation applies to exactly the
+            // tasks that were on screen
+            if let Some(targets) = self.batch_targets(self.batch_subtasks) {
+                let message = format!(
+                    "Set due date '{}' on {}?",
+                    due_date,
+                    Self::describe_targets(&targets)
+                );
+                self.show_confirm_dialog(message, ConfirmAction::SetDueDates(targets, due_date));
+            }
+        } else if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.due_date = Some(due_date);
+                self.dirty = true;
             }
         }
     }
 
-    pub fn delete_all_completed(&mut self) {
-        let completed_count = self.tasks.iter().filter(|t| t.completed).count();
-        if completed_count > 0 {
-            let message = format!("Delete {} completed task(s)?", completed_count);
-            self.show_confirm_dialog(message, ConfirmAction::DeleteAllCompleted);
+    /// Starts a move of the selected task to a typed position. Positions are
+    /// only meaningful in manual order, so other sort modes refuse.
+    pub fn open_move_to(&mut self) {
+        if self.selected_task_id().is_none() {
+            return;
         }
+        if self.sort_mode != SortMode::Manual {
+            self.notify(
+                Level::Warn,
+                "Switch to manual sort (o) to move tasks by position",
+            );
+            return;
+        }
+        self.move_input.clear();
+        self.mode = AppMode::MoveTo;
     }
 
-    pub fn filter_tasks(&self) -> Vec<Task> {
-        if self.search_input.is_empty() {
-            return self.tasks.clone();
+    /// Moves the selected top-level task to the typed 1-based position in the
+    /// displayed list, shifting the tasks in between. Out-of-range positions
+    /// are ignored.
+    pub fn move_selected_to_position(&mut self) {
+        self.mode = AppMode::Normal;
+        let position = std::mem::take(&mut self.move_input).parse::<usize>().ok();
+        let Some(task_id) = self.selected_task_id() else {
+            return;
+        };
+        let displayed = self.get_displayed_tasks();
+        let Some(target_id) = position
+            .filter(|position| *position >= 1)
+            .and_then(|position| displayed.get(position - 1))
+            .map(|task| task.id)
+        else {
+            return;
+        };
+        if target_id == task_id {
+            return;
         }
 
-        let search_lower = self.search_input.to_lowercase();
-        self.tasks
-            .iter()
-            .filter(|task| {
-                // Filter by description (case-insensitive)
-                task.description.to_lowercase().contains(&search_lower)
-                    // Filter by tags (case-insensitive)
-                    || task.tags.iter().any(|tag| tag.to_lowercase().contains(&search_lower))
-                    // Filter by priority
-                    || match search_lower.as_str() {
-                        "high" | "h" => matches!(task.priority, Priority::High),
-                        "medium" | "med" | "m" => matches!(task.priority, Priority::Medium),
-                        "low" | "l" => matches!(task.priority, Priority::Low),
-                        _ => false,
-                    }
-                    // Filter by completion status
-                    || match search_lower.as_str() {
-                        "completed" | "done" | "finished" => task.completed,
-                        "incomplete" | "pending" | "todo" => !task.completed,
-                        _ => false,
-                    }
-                    // Filter by due date (if it exists)
-                    || task.due_date.as_ref().is_some_and(|date| date.contains(&search_lower))
-                    // Filter by subtasks content
-                    || task.sub_tasks.iter().any(|subtask| {
-                        subtask.description.to_lowercase().contains(&search_lower)
-                            || subtask.tags.iter().any(|tag| tag.to_lowercase().contains(&search_lower))
-                    })
-            })
-            .cloned()
-            .collect()
+        self.move_task_to(task_id, target_id);
     }
 
-    pub fn get_displayed_tasks(&self) -> Vec<Task> {
-        let mut tasks = match self.mode {
-            AppMode::Search if !self.search_input.is_empty() => self.filter_tasks(),
-            _ => self.tasks.clone(),
+    /// Moves a top-level task to where `target_id` is, shifting the tasks in
+    /// between, and selects it.
+    fn move_task_to(&mut self, task_id: usize, target_id: usize) {
+        // Displayed order may skip hidden tasks, so land next to whichever
+        // task currently occupies the target position
+        let Some(from) = self.tasks.iter().position(|t| t.id == task_id) else {
+            return;
         };
+        let task = self.tasks.remove(from);
+        let target = self
+            .tasks
+            .iter()
+            .position(|t| t.id == target_id)
+            .unwrap_or(self.tasks.len());
+        let to = if from <= target { target + 1 } else { target };
+        self.tasks.insert(to, task);
+        self.dirty = true;
+        self.select_task_ref(TaskRef {
+            task_id,
+            subtask_id: None,
+        });
+    }
 
-        // Apply focus mode filter
-        if self.focus_mode {
-            tasks.retain(|task| !task.completed);
+    /// The display row drawn at a terminal position, if any.
+    pub fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        if column < area.x || column >= area.right() || row < area.y || row >= area.bottom() {
+            return None;
         }
+        let index = self.list_offset + (row - area.y) as usize;
+        (index < self.display_rows().len()).then_some(index)
+    }
 
-        tasks
+    /// Selects the clicked row and, if it is a top-level task, starts
+    /// dragging it. Dragging is only allowed in manual order with no filters,
+    /// where a drop position is unambiguous.
+    pub fn start_drag(&mut self, row: usize) {
+        self.drag = None;
+        self.state.select(Some(row));
+        let Some(DisplayRow::Task { task, .. }) = self.display_rows().into_iter().nth(row) else {
+            return;
+        };
+        if self.sort_mode != SortMode::Manual || !self.filters.is_empty() || self.group_completed {
+            self.notify(
+                Level::Warn,
+                "Dragging needs manual sort, no filters and no completed grouping",
+            );
+            return;
+        }
+        self.drag = Some(Drag {
+            task_id: task.id,
+            target: row,
+        });
     }
 
-    pub fn toggle_focus_mode(&mut self) {
-        self.focus_mode = !self.focus_mode;
-        // Reset selection when toggling focus mode
-        let displayed_tasks = self.get_displayed_tasks();
-        if displayed_tasks.is_empty() {
-            self.state.select(None);
-        } else {
-            self.state.select(Some(0));
+    pub fn drag_to(&mut self, row: usize) {
+        if let Some(drag) = &mut self.drag {
+            drag.target = row;
         }
     }
 
-    pub fn show_confirm_dialog(&mut self, message: String, action: ConfirmAction) {
-        self.confirm_dialog = Some(ConfirmDialog { message, action });
-        self.mode = AppMode::Confirm;
+    /// Drops the dragged task at the target row's task; a subtask row counts
+    /// as its parent.
+    pub fn end_drag(&mut self) {
+        let Some(drag) = self.drag.take() else {
+            return;
+        };
+        let target_id = match self.display_rows().into_iter().nth(drag.target) {
+            Some(DisplayRow::Task { task, .. }) => task.id,
+            Some(DisplayRow::Subtask { parent_id, .. }) => parent_id,
+            _ => return,
+        };
+        if target_id != drag.task_id {
+            self.move_task_to(drag.task_id, target_id);
+        }
     }
 
-    pub fn hide_confirm_dialog(&mut self) {
-        self.confirm_dialog = None;
-        self.mode = AppMode::Normal;
+    /// Starts timing the selected task, or stops its timer if it is the one
+    /// running. Starting a timer stops (and records) any other first.
+    pub fn toggle_timer(&mut self) {
+        let Some(task_ref) = self.selected_task_ref() else {
+            return;
+        };
+        let was_running = self.timer.is_some_and(|(running, _)| running == task_ref);
+        self.stop_timer();
+        if !was_running {
+            self.timer = Some((task_ref, Instant::now()));
+        }
     }
 
-    pub fn execute_confirm_action(&mut self) {
-        if let Some(dialog) = &self.confirm_dialog {
-            match &dialog.action {
-                ConfirmAction::DeleteTask(task_index) => {
-                    if *task_index < self.tasks.len() {
-                        self.tasks.remove(*task_index);
-                        let displayed_tasks = self.get_displayed_tasks();
-                        if !displayed_tasks.is_empty() {
-                            let new_index = (*task_index).min(displayed_tasks.len() - 1);
-                            self.state.select(Some(new_index));
-                        } else {
-                            self.state.select(None);
-                        }
-                    }
-                }
-                ConfirmAction::DeleteAllCompleted => {
-                    self.tasks.retain(|task| !task.completed);
-                    let displayed_tasks = self.get_displayed_tasks();
-                    if !displayed_tasks.is_empty() {
-                        self.state.select(Some(0));
-                    } else {
-                        self.state.select(None);
-                    }
-                }
+    /// Adds the running timer's elapsed time to its task.
+    pub fn stop_timer(&mut self) {
+        if let Some((task_ref, started)) = self.timer.take() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.time_spent += started.elapsed();
+                self.dirty = true;
             }
         }
-        self.hide_confirm_dialog();
     }
 
-    pub fn show_help(&mut self) {
-        self.mode = AppMode::Help;
+    /// The timed task and its total time so far, including the running
+    /// stretch that hasn't been recorded yet.
+    pub fn timer_status(&self) -> Option<(&Task, Duration)> {
+        let (task_ref, started) = self.timer?;
+        let task = self.task(task_ref)?;
+        Some((task, task.time_spent + started.elapsed()))
     }
 
-    pub fn hide_help(&mut self) {
-        self.mode = AppMode::Normal;
+    pub fn open_estimate(&mut self) {
+        if let Some(task) = self.selected_task_ref().and_then(|r| self.task(r)) {
+            self.estimate_input = task.estimate.map(format_duration).unwrap_or_default();
+            self.mode = AppMode::EstimateInput;
+        }
     }
 
-    pub fn cycle_theme(&mut self) {
-        let available_themes = self.theme_manager.get_available_themes();
-        if available_themes.is_empty() {
-            return;
+    /// Sets the typed estimate on the selected task; an empty input clears it.
+    pub fn set_estimate(&mut self) {
+        self.mode = AppMode::Normal;
+        let input = std::mem::take(&mut self.estimate_input);
+        let estimate = if input.trim().is_empty() {
+            None
+        } else {
+            match parse_duration(&input) {
+                Some(estimate) => Some(estimate),
+                None => {
+                    self.notify(
+                        Level::Warn,
+                        format!("Couldn't read '{}' as a duration (try 1h30m)", input),
+                    );
+                    return;
+                }
+            }
+        };
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.estimate = estimate;
+                self.dirty = true;
+            }
         }
+    }
 
-        let current_theme_name = &self.theme_manager.get_current_theme().name;
+    pub fn open_effort(&mut self) {
+        if let Some(task) = self.selected_task_ref().and_then(|r| self.task(r)) {
+            self.effort_input = task.effort.map(Effort::label).unwrap_or_default();
+            self.mode = AppMode::EffortInput;
+        }
+    }
 
-        // Find current theme index by matching theme names
-        let current_index = available_themes
-            .iter()
-            .position(|theme_key| {
-                if let Some(theme) = self.theme_manager.get_theme_by_key(theme_key) {
-                    theme.name == *current_theme_name
-                } else {
-                    false
+    /// Sets the typed effort on the selected task; an empty input clears it.
+    pub fn set_effort(&mut self) {
+        self.mode = AppMode::Normal;
+        let input = std::mem::take(&mut self.effort_input);
+        let effort = if input.trim().is_empty() {
+            None
+        } else {
+            match Effort::parse(&input) {
+                Some(effort) => Some(effort),
+                None => {
+                    self.notify(
+                        Level::Warn,
+                        format!("Couldn't read '{}' as an effort (try S, M, L or 5)", input),
+                    );
+                    return;
                 }
-            })
-            .unwrap_or(0);
+            }
+        };
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.effort = effort;
+                self.dirty = true;
+            }
+        }
+    }
 
-        let next_index = (current_index + 1) % available_themes.len();
-        let next_theme_name = &available_themes[next_index];
+    pub fn open_recurrence(&mut self) {
+        if let Some(task) = self.selected_task_ref().and_then(|r| self.task(r)) {
+            self.recurrence_input = task.recurrence.map(Recurrence::label).unwrap_or_default();
+            self.mode = AppMode::RecurrenceInput;
+        }
+    }
 
-        if self.theme_manager.set_theme(next_theme_name).is_err() {
-            // If setting fails, try to set to the first available theme
-            if let Some(first_theme) = available_themes.first() {
-                let _ = self.theme_manager.set_theme(first_theme);
+    /// Sets the typed recurrence on the selected task; an empty input stops
+    /// it recurring.
+    pub fn set_recurrence(&mut self) {
+        self.mode = AppMode::Normal;
+        let input = std::mem::take(&mut self.recurrence_input);
+        let recurrence = if input.trim().is_empty() {
+            None
+        } else {
+            match Recurrence::parse(&input) {
+                Some(recurrence) => Some(recurrence),
+                None => {
+                    self.notify(
+                        Level::Warn,
+                        format!(
+                            "Couldn't read '{}' as a recurrence (try daily, weekly, monthly or 3d)",
+                            input
+                        ),
+                    );
+                    return;
+                }
+            }
+        };
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.recurrence = recurrence;
+                self.dirty = true;
             }
         }
     }
 
-    pub fn get_available_theme_names(&self) -> Vec<String> {
-        self.theme_manager
+    /// After a recurring task is completed, adds its next occurrence right
+    /// after it: a reset copy due one step after the completed one (or after
+    /// today, if it had no due date). The copy takes the recurrence over, so
+    /// reopening and completing the old one doesn't add another.
+    fn add_next_occurrence(&mut self, task_ref: TaskRef) {
+        let Some(task) = self.task_mut(task_ref) else {
+            return;
+        };
+        if !task.is_done() {
+            return;
+        }
+        let Some(recurrence) = task.recurrence.take() else {
+            return;
+        };
+        let mut next = task.clone();
+        next.recurrence = Some(recurrence);
+        let now = Local::now();
+        next.due_date = match &next.due_date {
+            Some(due_date) => {
+                advance_due_date(due_date, recurrence).or_else(|| Some(due_date.clone()))
+            }
+            None => Some(
+                recurrence
+                    .next_after(now.date_naive())
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            ),
+        };
+        let reset = |task: &mut Task| {
+            task.status = Status::Todo;
+            task.completed_at = None;
+            task.time_spent = Duration::ZERO;
+            task.created_at = now;
+        };
+        reset(&mut next);
+        next.sub_tasks.iter_mut().for_each(reset);
+        self.insert_after(task_ref, next);
+        self.dirty = true;
+    }
+
+    /// Opens the notes editor for the selected task, pre-filled with its notes.
+    pub fn open_notes(&mut self) {
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task(task_ref) {
+                self.notes_input = task.notes.clone().unwrap_or_default();
+                self.mode = AppMode::Notes;
+            }
+        }
+    }
+
+    pub fn save_notes(&mut self) {
+        if let Some(task_ref) = self.selected_task_ref() {
+            let notes = std::mem::take(&mut self.notes_input);
+            let notes = if notes.trim().is_empty() {
+                None
+            } else {
+                Some(notes)
+            };
+            if let Some(task) = self.task_mut(task_ref) {
+                if task.notes != notes {
+                    task.notes = notes;
+                    self.dirty = true;
+                }
+            }
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    /// Pins or unpins the selected top-level task.
+    pub fn toggle_pinned(&mut self) {
+        if let Some(task_id) = self.selected_task_id() {
+            if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+                task.pinned = !task.pinned;
+                self.dirty = true;
+            }
+            self.select_task_ref(TaskRef {
+                task_id,
+                subtask_id: None,
+            });
+        }
+    }
+
+    /// Opens a web link from the selected task's description or notes in the
+    /// system browser. Pressing it again on the same task opens the next
+    /// link, wrapping around.
+    pub fn open_link(&mut self) {
+        let Some(task_ref) = self.selected_task_ref() else {
+            return;
+        };
+        let Some(task) = self.task(task_ref) else {
+            return;
+        };
+        let mut links: Vec<String> = find_urls(&task.description)
+            .into_iter()
+            .map(|range| task.description[range].to_string())
+            .collect();
+        if let Some(notes) = &task.notes {
+            links.extend(
+                find_urls(notes)
+                    .into_iter()
+                    .map(|range| notes[range].to_string()),
+            );
+        }
+        if links.is_empty() {
+            self.notify(Level::Info, "No link in this task");
+            return;
+        }
+
+        let index = match self.link_cycle {
+            Some((cycled, next)) if cycled == task_ref => next % links.len(),
+            _ => 0,
+        };
+        self.link_cycle = Some((task_ref, index + 1));
+        let url = &links[index];
+        match open_url(url) {
+            Ok(()) if links.len() > 1 => self.notify(
+                Level::Info,
+                format!(
+                    "Opened {} ({}/{}, O for the next)",
+                    url,
+                    index + 1,
+                    links.len()
+                ),
+            ),
+            Ok(()) => self.notify(Level::Info, format!("Opened {}", url)),
+            Err(e) => self.notify(Level::Error, format!("Failed to open {}: {}", url, e)),
+        }
+    }
+
+    /// Hides or shows the selected task's subtasks. Collapsing from a subtask
+    /// folds its parent and moves the selection up to it.
+    pub fn set_collapsed(&mut self, collapsed: bool) {
+        let Some(task_id) = self.selected_task_id() else {
+            return;
+        };
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+            if task.sub_tasks.is_empty() || task.collapsed == collapsed {
+                return;
+            }
+            task.collapsed = collapsed;
+            self.dirty = true;
+        }
+        if collapsed {
+            self.select_task_ref(TaskRef {
+                task_id,
+                subtask_id: None,
+            });
+        }
+    }
+
+    /// Collapses or expands every task with subtasks, like folding an
+    /// outline. A selected subtask hands the selection to its parent.
+    pub fn set_all_collapsed(&mut self, collapsed: bool) {
+        let selected = self.selected_task_ref();
+        for task in self.tasks.iter_mut() {
+            if !task.sub_tasks.is_empty() && task.collapsed != collapsed {
+                task.collapsed = collapsed;
+                self.dirty = true;
+            }
+        }
+        self.reselect(selected.map(|task_ref| TaskRef {
+            task_id: task_ref.task_id,
+            subtask_id: task_ref.subtask_id.filter(|_| !collapsed),
+        }));
+    }
+
+    /// Whether any of the task's blockers is still open.
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.blocked_by.iter().any(|id| {
+            self.tasks
+                .iter()
+                .any(|blocker| blocker.id == *id && !blocker.is_done())
+        })
+    }
+
+    /// Opens the picker for choosing which tasks block the selected one.
+    pub fn open_dependency_picker(&mut self) {
+        if let Some(task_id) = self.selected_task_id() {
+            self.dependency_target = Some(task_id);
+            self.dependency_picker_index = 0;
+            if self.dependency_candidates().is_empty() {
+                self.notify(Level::Warn, "No other tasks to depend on");
+                self.dependency_target = None;
+            } else {
+                self.mode = AppMode::DependencyPicker;
+            }
+        }
+    }
+
+    /// Top-level tasks the dependency target could be blocked by.
+    pub fn dependency_candidates(&self) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|t| Some(t.id) != self.dependency_target)
+            .collect()
+    }
+
+    pub fn dependency_picker_next(&mut self) {
+        let len = self.dependency_candidates().len();
+        if len > 0 {
+            self.dependency_picker_index = (self.dependency_picker_index + 1) % len;
+        }
+    }
+
+    pub fn dependency_picker_previous(&mut self) {
+        let len = self.dependency_candidates().len();
+        if len > 0 {
+            self.dependency_picker_index = (self.dependency_picker_index + len - 1) % len;
+        }
+    }
+
+    /// Adds or removes the highlighted candidate as a blocker of the target.
+    pub fn toggle_dependency(&mut self) {
+        let Some(target_id) = self.dependency_target else {
+            return;
+        };
+        let Some(blocker_id) = self
+            .dependency_candidates()
+            .get(self.dependency_picker_index)
+            .map(|t| t.id)
+        else {
+            return;
+        };
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == target_id) {
+            if let Some(pos) = task.blocked_by.iter().position(|id| *id == blocker_id) {
+                task.blocked_by.remove(pos);
+            } else {
+                task.blocked_by.push(blocker_id);
+            }
+            self.dirty = true;
+        }
+    }
+
+    pub fn close_dependency_picker(&mut self) {
+        self.dependency_target = None;
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn open_color_picker(&mut self) {
+        if self.selected_task_ref().is_some() {
+            self.color_picker_index = 0;
+            self.mode = AppMode::ColorPicker;
+        }
+    }
+
+    /// Opens the theme editor on the current theme, remembering it so that
+    /// leaving without saving puts it back.
+    pub fn open_theme_editor(&mut self) {
+        self.theme_backup = Some(self.theme_manager.get_current_theme().clone());
+        self.theme_editor_index = 0;
+        self.theme_color_input = None;
+        self.mode = AppMode::ThemeEditor;
+    }
+
+    /// Sets the highlighted slot to the typed color; the list redraws with
+    /// it straight away.
+    fn apply_theme_color(&mut self) {
+        let input = self.theme_color_input.take().unwrap_or_default();
+        let Some(color) = parse_color(&input) else {
+            self.theme_color_input = Some(input);
+            self.notify(Level::Warn, "Colors look like #89dceb or 137,220,235");
+            return;
+        };
+        if let Some(slot) = self
+            .theme_manager
+            .current_theme_mut()
+            .color_mut(self.theme_editor_index)
+        {
+            *slot = color;
+        }
+    }
+
+    /// Saves the edited theme as a custom theme: an edited "Nord" becomes
+    /// "Nord (edited)", stored as `nord-edited.json`.
+    fn save_edited_theme(&mut self) {
+        let theme = self.theme_manager.current_theme_mut();
+        if !theme.name.ends_with("(edited)") {
+            theme.name.push_str(" (edited)");
+        }
+        let key = theme
+            .name
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        match self.theme_manager.save_current_theme(&key) {
+            Ok(path) => {
+                self.theme_backup = Some(self.theme_manager.get_current_theme().clone());
+                self.notify(Level::Info, format!("Saved theme to {}", path.display()));
+            }
+            Err(e) => self.notify(Level::Error, format!("Failed to save theme: {}", e)),
+        }
+    }
+
+    /// Leaves the theme editor, dropping any edits made since the last save.
+    fn close_theme_editor(&mut self) {
+        if let Some(theme) = self.theme_backup.take() {
+            *self.theme_manager.current_theme_mut() = theme;
+        }
+        self.theme_color_input = None;
+        self.mode = AppMode::Normal;
+    }
+
+    /// Lists the saved filter presets, re-read in case another instance
+    /// saved some.
+    pub fn open_preset_picker(&mut self) {
+        self.filter_presets = load_filter_presets();
+        self.preset_picker_index = 0;
+        self.preset_name_input = None;
+        self.mode = AppMode::PresetPicker;
+    }
+
+    pub fn preset_picker_next(&mut self) {
+        if !self.filter_presets.is_empty() {
+            self.preset_picker_index = (self.preset_picker_index + 1) % self.filter_presets.len();
+        }
+    }
+
+    pub fn preset_picker_previous(&mut self) {
+        let len = self.filter_presets.len();
+        if len > 0 {
+            self.preset_picker_index = (self.preset_picker_index + len - 1) % len;
+        }
+    }
+
+    fn apply_picked_preset(&mut self) {
+        if let Some(preset) = self.filter_presets.get(self.preset_picker_index).cloned() {
+            self.apply_filter_preset(&preset);
+            self.notify(Level::Info, format!("Applied preset '{}'", preset.name));
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    /// Replaces the active filters with the preset's.
+    pub fn apply_filter_preset(&mut self, preset: &FilterPreset) {
+        let selected = self.selected_task_ref();
+        self.filters.clear();
+        self.search_scope = None;
+        self.add_search_filters(&preset.query);
+        if preset.hide_completed {
+            self.filters.push(ActiveFilter::HideCompleted);
+        }
+        if preset.focus {
+            self.filters.push(ActiveFilter::Focus);
+        }
+        self.reselect(selected);
+    }
+
+    /// Applies the saved preset with this name (ignoring case), for the
+    /// `--preset` flag.
+    pub fn apply_filter_preset_named(&mut self, name: &str) -> Result<(), String> {
+        let preset = self
+            .filter_presets
+            .iter()
+            .find(|preset| preset.name.to_lowercase() == name.to_lowercase())
+            .cloned()
+            .ok_or_else(|| format!("No filter preset named '{}'", name))?;
+        self.apply_filter_preset(&preset);
+        Ok(())
+    }
+
+    fn start_naming_preset(&mut self) {
+        if self.current_filter_preset(String::new()).is_none() {
+            self.notify(
+                Level::Warn,
+                "Filter the list (/ or f) first to save a preset",
+            );
+            return;
+        }
+        self.preset_name_input = Some(String::new());
+    }
+
+    /// The active filters as a preset, or `None` when there are none to
+    /// save. Scoping to one task is left out, since it names a task.
+    fn current_filter_preset(&self, name: String) -> Option<FilterPreset> {
+        let query = self
+            .filters
+            .iter()
+            .filter(|filter| matches!(filter, ActiveFilter::Search(_)))
+            .map(ActiveFilter::label)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let preset = FilterPreset {
+            name,
+            query,
+            hide_completed: self.filters.contains(&ActiveFilter::HideCompleted),
+            focus: self.focus_mode(),
+        };
+        (!preset.query.is_empty() || preset.hide_completed || preset.focus).then_some(preset)
+    }
+
+    /// Saves the active filters under the typed name, replacing a preset
+    /// with the same name.
+    fn save_filter_preset(&mut self) {
+        let name = self.preset_name_input.take().unwrap_or_default();
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        let Some(preset) = self.current_filter_preset(name.to_string()) else {
+            return;
+        };
+        let existing = self
+            .filter_presets
+            .iter()
+            .position(|p| p.name.to_lowercase() == name.to_lowercase());
+        let index = match existing {
+            Some(index) => {
+                self.filter_presets[index] = preset;
+                index
+            }
+            None => {
+                self.filter_presets.push(preset);
+                self.filter_presets.len() - 1
+            }
+        };
+        self.preset_picker_index = index;
+        match save_filter_presets(&self.filter_presets) {
+            Ok(()) => self.notify(Level::Info, format!("Saved preset '{}'", name)),
+            Err(e) => self.notify(Level::Error, format!("Failed to save presets: {}", e)),
+        }
+    }
+
+    fn delete_picked_preset(&mut self) {
+        if self.preset_picker_index >= self.filter_presets.len() {
+            return;
+        }
+        let preset = self.filter_presets.remove(self.preset_picker_index);
+        self.preset_picker_index = self
+            .preset_picker_index
+            .min(self.filter_presets.len().saturating_sub(1));
+        match save_filter_presets(&self.filter_presets) {
+            Ok(()) => self.notify(Level::Info, format!("Deleted preset '{}'", preset.name)),
+            Err(e) => self.notify(Level::Error, format!("Failed to save presets: {}", e)),
+        }
+    }
+
+    /// Number of picker entries: "none" followed by the theme palette
+    pub fn color_picker_len(&self) -> usize {
+        self.theme_manager.get_current_theme().palette().len() + 1
+    }
+
+    pub fn color_picker_next(&mut self) {
+        self.color_picker_index = (self.color_picker_index + 1) % self.color_picker_len();
+    }
+
+    pub fn color_picker_previous(&mut self) {
+        let len = self.color_picker_len();
+        self.color_picker_index = (self.color_picker_index + len - 1) % len;
+    }
+
+    /// Applies the highlighted palette color (or clears it) on the selected task.
+    pub fn apply_picked_color(&mut self) {
+        let color = self
+            .color_picker_index
+            .checked_sub(1)
+            .and_then(|i| {
+                self.theme_manager
+                    .get_current_theme()
+                    .palette()
+                    .get(i)
+                    .map(|(_, color)| *color)
+            })
+            .map(SerializableColor::from);
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task_mut(task_ref) {
+                task.color = color;
+                self.dirty = true;
+            }
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn delete_task(&mut self) {
+        if let Some(task_ref) = self.selected_task_ref() {
+            if let Some(task) = self.task(task_ref) {
+                let kind = if task_ref.subtask_id.is_some() {
+                    "subtask"
+                } else {
+                    "task"
+                };
+                let subtask_count = task.sub_tasks.len();
+                if subtask_count > 0 && self.settings.confirm.delete_with_subtasks {
+                    let message = format!(
+                        "Delete '{}' and its {} subtask{}?",
+                        task.description,
+                        subtask_count,
+                        if subtask_count == 1 { "" } else { "s" }
+                    );
+                    self.show_confirm_dialog(
+                        message,
+                        ConfirmAction::DeleteWithSubtasks(task_ref.task_id),
+                    );
+                } else if subtask_count == 0 && self.settings.confirm.delete_task {
+                    let message = format!("Delete {}: '{}'?", kind, task.description);
+                    self.show_confirm_dialog(message, ConfirmAction::DeleteTask(task_ref));
+                } else {
+                    self.remove_task(task_ref);
+                    self.ensure_valid_selection();
+                }
+            }
+        }
+    }
+
+    /// Adds a top-level task parsed from `text` exactly as if it had been
+    /// typed into the add popup. Text with no description (blank, or only
+    /// tags) is skipped; returns whether a task was added.
+    pub fn add_task_from_text(&mut self, text: &str) -> bool {
+        if !has_description(text) {
+            return false;
+        }
+        // Same clean-up as a paste into the add popup
+        self.input = text
+            .split_whitespace()
+            .map(|word| word.chars().filter(|c| !c.is_control()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.adding_subtask = false;
+        self.insert_task();
+        true
+    }
+
+    /// Adds a copy of the named template from the config directory as a new
+    /// top-level task. Relative due dates in the template ("today",
+    /// "tomorrow at 9am") are resolved now, at instantiation time.
+    pub fn add_from_template(&mut self, name: &str) -> Result<(), String> {
+        let path = template_path(name).map_err(|e| e.to_string())?;
+        let mut task = load_template(&path)
+            .map_err(|e| format!("Failed to load template {:?}: {}", path, e))?;
+
+        let now = Local::now();
+        task.id = self.allocate_id();
+        self.reset_instantiated(&mut task, now);
+        for (index, sub_task) in task.sub_tasks.iter_mut().enumerate() {
+            sub_task.id = index + 1;
+            self.reset_instantiated(sub_task, now);
+            sub_task.sub_tasks.clear();
+        }
+        // Template IDs don't refer to anything in this list
+        task.blocked_by.clear();
+
+        self.tasks.push(task);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn reset_instantiated(&self, task: &mut Task, now: DateTime<Local>) {
+        task.status = Status::Todo;
+        task.completed_at = None;
+        task.time_spent = Duration::ZERO;
+        task.created_at = now;
+        if let Some(due_date) = &task.due_date {
+            if let (_, Some(resolved)) = self.extract_date_and_clean_description(due_date) {
+                task.due_date = Some(resolved);
+            }
+        }
+    }
+
+    /// Copies the selected task (with its subtasks) right after the original,
+    /// resetting completion, and selects the copy so it can be edited.
+    pub fn duplicate_task(&mut self) {
+        let Some(task_ref) = self.selected_task_ref() else {
+            return;
+        };
+        let Some(mut copy) = self.task(task_ref).cloned() else {
+            return;
+        };
+        let now = Local::now();
+        copy.status = Status::Todo;
+        copy.completed_at = None;
+        copy.time_spent = Duration::ZERO;
+        copy.created_at = now;
+        for sub_task in copy.sub_tasks.iter_mut() {
+            sub_task.status = Status::Todo;
+            sub_task.completed_at = None;
+            sub_task.time_spent = Duration::ZERO;
+            sub_task.created_at = now;
+        }
+
+        let Some(new_ref) = self.insert_after(task_ref, copy) else {
+            return;
+        };
+        self.dirty = true;
+        self.select_task_ref(new_ref);
+    }
+
+    /// Inserts `task` right after the task or subtask at `task_ref`, giving it
+    /// a fresh ID among its siblings.
+    fn insert_after(&mut self, task_ref: TaskRef, mut task: Task) -> Option<TaskRef> {
+        match task_ref.subtask_id {
+            None => {
+                let index = self.tasks.iter().position(|t| t.id == task_ref.task_id)?;
+                task.id = self.allocate_id();
+                let new_ref = TaskRef {
+                    task_id: task.id,
+                    subtask_id: None,
+                };
+                self.tasks.insert(index + 1, task);
+                Some(new_ref)
+            }
+            Some(subtask_id) => {
+                let parent = self.tasks.iter_mut().find(|t| t.id == task_ref.task_id)?;
+                let index = parent.sub_tasks.iter().position(|t| t.id == subtask_id)?;
+                task.id = parent.sub_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+                let new_ref = TaskRef {
+                    task_id: parent.id,
+                    subtask_id: Some(task.id),
+                };
+                parent.sub_tasks.insert(index + 1, task);
+                Some(new_ref)
+            }
+        }
+    }
+
+    /// Moves the selection onto the row showing `task_ref`, if it is visible.
+    pub fn select_task_ref(&mut self, task_ref: TaskRef) {
+        let position = self
+            .display_rows()
+            .iter()
+            .position(|row| row.task_ref() == Some(task_ref));
+        if position.is_some() {
+            self.state.select(position);
+        }
+    }
+
+    /// Removes a task or subtask from the tree, returning it if it existed.
+    fn remove_task(&mut self, task_ref: TaskRef) -> Option<Task> {
+        let index = self.tasks.iter().position(|t| t.id == task_ref.task_id)?;
+        let removed = match task_ref.subtask_id {
+            Some(subtask_id) => {
+                let sub_tasks = &mut self.tasks[index].sub_tasks;
+                let sub_index = sub_tasks.iter().position(|t| t.id == subtask_id)?;
+                sub_tasks.remove(sub_index)
+            }
+            None => self.tasks.remove(index),
+        };
+        prune_dependencies(&mut self.tasks);
+        self.dirty = true;
+        Some(removed)
+    }
+
+    /// Deletes a top-level task but keeps its subtasks, which take its place
+    /// in the list as top-level tasks with fresh IDs.
+    fn delete_promoting_subtasks(&mut self, task_id: usize) {
+        let Some(index) = self.tasks.iter().position(|t| t.id == task_id) else {
+            return;
+        };
+        let task = self.tasks.remove(index);
+        for (offset, mut sub_task) in task.sub_tasks.into_iter().enumerate() {
+            sub_task.id = self.allocate_id();
+            self.tasks.insert(index + offset, sub_task);
+        }
+        prune_dependencies(&mut self.tasks);
+        self.dirty = true;
+        self.ensure_valid_selection();
+    }
+
+    pub fn delete_all_completed(&mut self) {
+        let completed_count = self.tasks.iter().filter(|t| t.is_done()).count();
+        if completed_count == 0 {
+            return;
+        }
+        if self.settings.confirm.clear_completed {
+            let message = format!("Delete {} completed task(s)?", completed_count);
+            self.show_confirm_dialog(message, ConfirmAction::DeleteAllCompleted);
+        } else {
+            self.remove_completed();
+        }
+    }
+
+    fn remove_completed(&mut self) {
+        self.tasks.retain(|task| !task.is_done());
+        prune_dependencies(&mut self.tasks);
+        self.dirty = true;
+        self.select_first();
+    }
+
+    /// Records the current search in the history, skipping empty queries and
+    /// repeats of the most recent one.
+    pub fn commit_search(&mut self) {
+        self.search_history_index = None;
+        let query = self.search_input.trim();
+        if query.is_empty() || self.search_history.last().is_some_and(|last| last == query) {
+            return;
+        }
+        self.search_history.push(query.to_string());
+        if self.search_history.len() > Self::SEARCH_HISTORY_LIMIT {
+            let excess = self.search_history.len() - Self::SEARCH_HISTORY_LIMIT;
+            self.search_history.drain(..excess);
+        }
+        if let Err(e) = save_search_history(&self.search_history) {
+            self.notify(
+                Level::Error,
+                format!("Failed to save search history: {}", e),
+            );
+        }
+    }
+
+    /// Steps back to an older search, like Up in a shell.
+    pub fn recall_previous_search(&mut self) {
+        if self.search_history.is_empty() {
+            return;
+        }
+        let index = match self.search_history_index {
+            Some(index) => index.saturating_sub(1),
+            None => self.search_history.len() - 1,
+        };
+        self.search_history_index = Some(index);
+        self.search_input = self.search_history[index].clone();
+        self.ensure_valid_selection();
+    }
+
+    /// Steps forward to a newer search; past the newest the query is cleared.
+    pub fn recall_next_search(&mut self) {
+        let Some(index) = self.search_history_index else {
+            return;
+        };
+        if index + 1 < self.search_history.len() {
+            self.search_history_index = Some(index + 1);
+            self.search_input = self.search_history[index + 1].clone();
+        } else {
+            self.search_history_index = None;
+            self.search_input.clear();
+        }
+        self.ensure_valid_selection();
+    }
+
+    /// Turns the terms typed in the search popup into search filters, along
+    /// with the scope if one was chosen.
+    fn apply_search_input(&mut self) {
+        let query = std::mem::take(&mut self.search_input);
+        self.add_search_filters(&query);
+        if let Some(task_id) = self.search_scope.take() {
+            let description = self
+                .tasks
+                .iter()
+                .find(|t| t.id == task_id)
+                .map(|t| t.description.clone())
+                .unwrap_or_default();
+            self.filters
+                .retain(|filter| !matches!(filter, ActiveFilter::Scope(..)));
+            self.filters.push(ActiveFilter::Scope(task_id, description));
+        }
+    }
+
+    /// After a search, selects the first row that matched it, skipping any
+    /// parent listed only as context for its subtasks.
+    fn select_first_match(&mut self) {
+        let first = self
+            .display_rows()
+            .iter()
+            .find(|row| {
+                row.is_selectable() && !matches!(row, DisplayRow::Task { context: true, .. })
+            })
+            .and_then(DisplayRow::task_ref);
+        match first {
+            Some(task_ref) => self.select_task_ref(task_ref),
+            None => self.ensure_valid_selection(),
+        }
+    }
+
+    /// Limits the search being typed to the selected task's subtasks, or
+    /// lifts that limit again.
+    pub fn toggle_search_scope(&mut self) {
+        self.search_scope = match self.search_scope {
+            Some(_) => None,
+            None => self.selected_task_id(),
+        };
+    }
+
+    /// The task searches are currently limited to: the one being chosen in
+    /// the search popup, otherwise a committed scope filter.
+    pub fn scope_root(&self) -> Option<usize> {
+        self.search_scope.or_else(|| {
+            self.filters.iter().find_map(|filter| match filter {
+                ActiveFilter::Scope(task_id, _) => Some(*task_id),
+                _ => None,
+            })
+        })
+    }
+
+    pub fn add_search_filters(&mut self, query: &str) {
+        for term in search_terms(query) {
+            let filter = ActiveFilter::Search(term);
+            if !self.filters.contains(&filter) {
+                self.filters.push(filter);
+            }
+        }
+    }
+
+    pub fn has_search_filter(&self) -> bool {
+        self.filters
+            .iter()
+            .any(|filter| matches!(filter, ActiveFilter::Search(_)))
+    }
+
+    /// The committed search filters followed by whatever is being typed in
+    /// the search popup, so the list previews the search as it is entered.
+    pub fn active_search_terms(&self) -> Vec<String> {
+        let mut terms: Vec<String> = self
+            .filters
+            .iter()
+            .filter_map(|filter| match filter {
+                ActiveFilter::Search(term) => Some(term.clone()),
+                _ => None,
+            })
+            .collect();
+        terms.extend(search_terms(&self.search_input));
+        terms
+    }
+
+    pub fn focus_mode(&self) -> bool {
+        self.filters.contains(&ActiveFilter::Focus)
+    }
+
+    /// Whether completed tasks and subtasks are dropped from the list, which
+    /// focus mode implies.
+    pub fn hiding_completed(&self) -> bool {
+        self.focus_mode() || self.filters.contains(&ActiveFilter::HideCompleted)
+    }
+
+    /// Adds the filter if it isn't active, otherwise removes it.
+    fn toggle_filter(&mut self, filter: ActiveFilter) {
+        match self.filters.iter().position(|f| *f == filter) {
+            Some(index) => {
+                self.filters.remove(index);
+            }
+            None => self.filters.push(filter),
+        }
+    }
+
+    /// Removes the filter shown as chip `index` (zero-based).
+    pub fn remove_filter(&mut self, index: usize) {
+        if index < self.filters.len() {
+            let selected = self.selected_task_ref();
+            self.filters.remove(index);
+            self.reselect(selected);
+        }
+    }
+
+    /// Keeps the selection on the same task after the list changes, when it
+    /// is still visible.
+    fn reselect(&mut self, selected: Option<TaskRef>) {
+        match selected {
+            Some(task_ref)
+                if self
+                    .display_rows()
+                    .iter()
+                    .any(|row| row.task_ref() == Some(task_ref)) =>
+            {
+                self.select_task_ref(task_ref)
+            }
+            _ => self.ensure_valid_selection(),
+        }
+    }
+
+    /// Keeps the tasks that match every search term; see `search_terms`.
+    /// With a `root` task ID the search looks only inside that task: it is
+    /// kept as the sole top-level task with just its matching subtasks.
+    pub fn filter_tasks(&self, root: Option<usize>) -> Vec<Task> {
+        let terms = self.active_search_terms();
+        if let Some(root) = root {
+            let Some(task) = self.tasks.iter().find(|t| t.id == root) else {
+                return Vec::new();
+            };
+            let mut task = task.clone();
+            task.sub_tasks.retain(|sub_task| {
+                terms
+                    .iter()
+                    .all(|term| task_matches_term(sub_task, term, &self.settings))
+            });
+            return vec![task];
+        }
+        if terms.is_empty() {
+            return self.tasks.clone();
+        }
+
+        self.tasks
+            .iter()
+            .filter(|task| {
+                terms
+                    .iter()
+                    .all(|term| task_matches_term(task, term, &self.settings))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_displayed_tasks(&self) -> Vec<Task> {
+        // The search filter stays applied after leaving search mode until cleared
+        let mut tasks = self.filter_tasks(self.scope_root());
+
+        // Apply focus mode filter
+        if self.hiding_completed() {
+            tasks.retain(|task| !task.is_done());
+        }
+
+        match self.sort_mode {
+            SortMode::Manual => {}
+            SortMode::Created => tasks.sort_by_key(|task| task.created_at),
+            SortMode::Due => {
+                let date_only_time = self.settings.date_only_sort_time;
+                tasks.sort_by_key(|task| {
+                    let due = task
+                        .due_date
+                        .as_deref()
+                        .and_then(|due_date| due_sort_time(due_date, date_only_time));
+                    (due.is_none(), due)
+                })
+            }
+        }
+
+        // Pinned tasks go first and, when grouping, completed tasks go last;
+        // the sort is stable so each block keeps its relative order
+        if self.group_completed {
+            tasks.sort_by_key(|task| (task.is_done(), !task.pinned));
+        } else {
+            tasks.sort_by_key(|task| !task.pinned);
+        }
+
+        tasks
+    }
+
+    /// Flattens the displayed tasks and their subtasks into the rows drawn by
+    /// `render_tasks`, so selection indices map onto exactly what is on screen.
+    pub fn display_rows(&self) -> Vec<DisplayRow> {
+        let mut rows = Vec::new();
+        let mut completed_header_added = false;
+        // A scoped search already lists only the matching subtasks
+        let terms = if self.scope_root().is_some() {
+            Vec::new()
+        } else {
+            self.active_search_terms()
+        };
+
+        for (index, task) in self.get_displayed_tasks().into_iter().enumerate() {
+            if index > 0 && self.density != Density::Compact {
+                rows.push(DisplayRow::Spacer);
+            }
+            if self.group_completed && task.is_done() && !completed_header_added {
+                rows.push(DisplayRow::Header("— Completed —".to_string()));
+                completed_header_added = true;
+            }
+
+            // Focus mode and hide-completed both drop completed subtasks; focus
+            // mode also hides every subtask of a completed parent. Collapsed
+            // tasks hide theirs too, except when searching inside the task
+            let hide_completed = self.hiding_completed();
+            let collapsed = task.collapsed && self.scope_root().is_none();
+            let mut sub_tasks: Vec<Task> = if collapsed || (self.focus_mode() && task.is_done()) {
+                Vec::new()
+            } else {
+                task.sub_tasks
+                    .iter()
+                    .filter(|sub_task| !(hide_completed && sub_task.is_done()))
+                    .cloned()
+                    .collect()
+            };
+
+            // A parent that only matched through its subtasks keeps just the
+            // ones that matched, unless none match every term on their own
+            let mut context = false;
+            if self.subtask_results
+                && !terms.is_empty()
+                && !terms
+                    .iter()
+                    .all(|term| fields_match_term(&task, term, &self.settings))
+            {
+                let matching: Vec<Task> = sub_tasks
+                    .iter()
+                    .filter(|sub_task| {
+                        terms
+                            .iter()
+                            .all(|term| fields_match_term(sub_task, term, &self.settings))
+                    })
+                    .cloned()
+                    .collect();
+                if !matching.is_empty() {
+                    sub_tasks = matching;
+                    context = true;
+                }
+            }
+
+            let parent_id = task.id;
+            rows.push(DisplayRow::Task {
+                task,
+                number: index + 1,
+                context,
+            });
+            for task in sub_tasks {
+                if self.density == Density::Spacious {
+                    rows.push(DisplayRow::Spacer);
+                }
+                rows.push(DisplayRow::Subtask { parent_id, task });
+            }
+        }
+
+        rows
+    }
+
+    /// The tasks as currently listed, each carrying only the subtasks that
+    /// are shown under it.
+    pub fn displayed_task_tree(&self) -> Vec<Task> {
+        let mut tasks: Vec<Task> = Vec::new();
+        for row in self.display_rows() {
+            match row {
+                DisplayRow::Task { mut task, .. } => {
+                    task.sub_tasks.clear();
+                    tasks.push(task);
+                }
+                DisplayRow::Subtask { task, .. } => {
+                    if let Some(parent) = tasks.last_mut() {
+                        parent.sub_tasks.push(task);
+                    }
+                }
+                DisplayRow::Header(_) | DisplayRow::Spacer => {}
+            }
+        }
+        tasks
+    }
+
+    /// Writes the tasks beside the tasks file (`tasks.md`, `tasks.csv`).
+    /// With `filtered_only` just the current view is exported, otherwise
+    /// every task.
+    pub fn export(&mut self, format: ExportFormat, filtered_only: bool) {
+        let tasks = if filtered_only {
+            self.displayed_task_tree()
+        } else {
+            self.tasks.clone()
+        };
+        let path = self.tasks_path.with_extension(format.extension());
+        match fs::write(&path, export_tasks(&tasks, format)) {
+            Ok(()) => self.notify(
+                Level::Info,
+                format!("Exported {} task(s) to {}", tasks.len(), path.display()),
+            ),
+            Err(e) => self.notify(
+                Level::Error,
+                format!("Failed to export to {}: {}", path.display(), e),
+            ),
+        }
+    }
+
+    pub fn toggle_focus_mode(&mut self) {
+        self.toggle_filter(ActiveFilter::Focus);
+        // Reset selection when toggling focus mode
+        self.select_first();
+    }
+
+    /// Shows or hides completed tasks, keeping the selection on the same task
+    /// when it is still visible.
+    pub fn toggle_hide_completed(&mut self) {
+        let selected = self.selected_task_ref();
+        self.toggle_filter(ActiveFilter::HideCompleted);
+        self.reselect(selected);
+    }
+
+    pub fn cycle_sort_mode(&mut self) {
+        self.sort_mode = match self.sort_mode {
+            SortMode::Manual => SortMode::Created,
+            SortMode::Created => SortMode::Due,
+            SortMode::Due => SortMode::Manual,
+        };
+        self.select_first();
+    }
+
+    pub fn toggle_group_completed(&mut self) {
+        self.group_completed = !self.group_completed;
+        self.select_first();
+    }
+
+    pub fn toggle_line_numbers(&mut self) {
+        self.show_line_numbers = !self.show_line_numbers;
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    pub fn show_confirm_dialog(&mut self, message: String, action: ConfirmAction) {
+        self.confirm_dialog = Some(ConfirmDialog { message, action });
+        self.mode = AppMode::Confirm;
+    }
+
+    pub fn hide_confirm_dialog(&mut self) {
+        let dialog = self.confirm_dialog.take();
+        self.mode = AppMode::Normal;
+        // Either answer to a duplicate in a run of adds goes back to the input
+        if let Some(ConfirmDialog {
+            action:
+                ConfirmAction::AddDuplicate {
+                    subtask,
+                    keep_open: true,
+                    ..
+                },
+            ..
+        }) = dialog
+        {
+            self.adding_subtask = subtask;
+            self.mode = AppMode::Insert;
+        }
+    }
+
+    pub fn execute_confirm_action(&mut self) {
+        if let Some(dialog) = &self.confirm_dialog {
+            match &dialog.action {
+                ConfirmAction::DeleteTask(task_ref) => {
+                    let task_ref = *task_ref;
+                    self.remove_task(task_ref);
+                    self.ensure_valid_selection();
+                }
+                ConfirmAction::DeleteWithSubtasks(task_id) => {
+                    let task_ref = TaskRef {
+                        task_id: *task_id,
+                        subtask_id: None,
+                    };
+                    self.remove_task(task_ref);
+                    self.ensure_valid_selection();
+                }
+                ConfirmAction::DeleteAllCompleted => self.remove_completed(),
+                ConfirmAction::AddDuplicate { input, subtask, .. } => {
+                    self.input = input.clone();
+                    self.adding_subtask = *subtask;
+                    self.insert_task();
+                }
+                ConfirmAction::SetDueDates(targets, due_date) => {
+                    let due_date = due_date.clone();
+                    self.edit_all(&targets.clone(), |task| {
+                        task.due_date = Some(due_date.clone())
+                    });
+                }
+                ConfirmAction::CompleteAll(targets) => {
+                    let targets = targets.clone();
+                    self.edit_all(&targets, |task| {
+                        if !task.is_done() {
+                            task.status = Status::Done;
+                            task.completed_at = Some(Local::now());
+                        }
+                    });
+                    for task_ref in targets {
+                        self.add_next_occurrence(task_ref);
+                    }
+                    self.ensure_valid_selection();
+                }
+                ConfirmAction::SetPriorities(targets, priority) => {
+                    let priority = priority.clone();
+                    self.edit_all(&targets.clone(), |task| task.priority = priority.clone());
+                }
+                ConfirmAction::AddTags(targets, tags) => {
+                    let tags = tags.clone();
+                    self.edit_all(&targets.clone(), |task| {
+                        for tag in &tags {
+                            if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                                task.tags.push(tag.clone());
+                            }
+                        }
+                    });
+                }
+                ConfirmAction::DeleteAll {
+                    task_ids,
+                    keep_subtasks,
+                } => {
+                    let (task_ids, keep_subtasks) = (task_ids.clone(), *keep_subtasks);
+                    for task_id in task_ids {
+                        if keep_subtasks {
+                            self.delete_promoting_subtasks(task_id);
+                        } else {
+                            self.remove_task(TaskRef {
+                                task_id,
+                                subtask_id: None,
+                            });
+                        }
+                    }
+                    self.ensure_valid_selection();
+                }
+            }
+        }
+        self.hide_confirm_dialog();
+    }
+
+    /// Applies one edit to each of the referenced tasks that still exists.
+    fn edit_all(&mut self, targets: &[TaskRef], mut edit: impl FnMut(&mut Task)) {
+        for task_ref in targets {
+            if let Some(task) = self.task_mut(*task_ref) {
+                edit(task);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// The alternative answer to a cascade-delete prompt: delete the task but
+    /// keep its subtasks.
+    pub fn promote_confirmed_subtasks(&mut self) {
+        if let Some(ConfirmDialog {
+            action: ConfirmAction::DeleteWithSubtasks(task_id),
+            ..
+        }) = self.confirm_dialog
+        {
+            self.delete_promoting_subtasks(task_id);
+            self.hide_confirm_dialog();
+        }
+    }
+
+    pub fn show_help(&mut self) {
+        self.help_scroll = 0;
+        self.mode = AppMode::Help;
+    }
+
+    /// Lines PageUp/PageDown move the help by: most of the popup's height,
+    /// which is 80% of the terminal less its borders.
+    fn help_page(&self) -> u16 {
+        (self.frame_size.height * 4 / 5).saturating_sub(3).max(1)
+    }
+
+    pub fn hide_help(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn show_stats(&mut self) {
+        self.mode = AppMode::Stats;
+    }
+
+    pub fn hide_stats(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    /// Switches the stats chart between the last week and the last month.
+    pub fn toggle_stats_range(&mut self) {
+        self.stats_days = if self.stats_days == 7 { 30 } else { 7 };
+    }
+
+    /// A line such as "You have 3 overdue and 5 due today" covering open
+    /// tasks and subtasks, or `None` when nothing is overdue or due today.
+    pub fn due_summary_text(&self) -> Option<String> {
+        let now = Local::now().naive_local();
+        let (mut overdue, mut today) = (0, 0);
+        let deadlines = self
+            .tasks
+            .iter()
+            .flat_map(|task| std::iter::once(task).chain(task.sub_tasks.iter()))
+            .filter(|task| !task.is_done())
+            .filter_map(|task| task.due_date.as_deref().and_then(due_deadline));
+        for deadline in deadlines {
+            if deadline < now {
+                overdue += 1;
+            } else if deadline.date() == now.date() {
+                today += 1;
+            }
+        }
+        let counts = match (overdue, today) {
+            (0, 0) => return None,
+            (overdue, 0) => format!("{} overdue", overdue),
+            (0, today) => format!("{} due today", today),
+            (overdue, today) => format!("{} overdue and {} due today", overdue, today),
+        };
+        Some(format!("You have {}", counts))
+    }
+
+    /// Completed and total task counts across the whole list, ignoring
+    /// filters, for the progress gauge. Subtasks count only with
+    /// `include_subtasks`.
+    pub fn completion_progress(&self, include_subtasks: bool) -> (usize, usize) {
+        let mut done = 0;
+        let mut total = 0;
+        for task in &self.tasks {
+            let sub_tasks: &[Task] = if include_subtasks {
+                &task.sub_tasks
+            } else {
+                &[]
+            };
+            for task in std::iter::once(task).chain(sub_tasks) {
+                total += 1;
+                if task.is_done() {
+                    done += 1;
+                }
+            }
+        }
+        (done, total)
+    }
+
+    /// Effort left on unfinished tasks and subtasks: their story points
+    /// summed, and how many are sized small, medium and large.
+    pub fn pending_effort(&self) -> (u32, [usize; 3]) {
+        let mut points = 0;
+        let mut sizes = [0; 3];
+        for task in &self.tasks {
+            for task in std::iter::once(task).chain(&task.sub_tasks) {
+                if task.is_done() {
+                    continue;
+                }
+                match task.effort {
+                    Some(Effort::Points(n)) => points += n,
+                    Some(Effort::Small) => sizes[0] += 1,
+                    Some(Effort::Medium) => sizes[1] += 1,
+                    Some(Effort::Large) => sizes[2] += 1,
+                    None => {}
+                }
+            }
+        }
+        (points, sizes)
+    }
+
+    /// Counts tasks and subtasks completed on each of the last `days` days,
+    /// oldest first. Tasks without a completion timestamp are ignored.
+    pub fn completions_per_day(&self, days: u32) -> Vec<(String, u64)> {
+        let today = Local::now().date_naive();
+        let label_format = if days <= 7 { "%a" } else { "%d" };
+        let completed_dates = self.completion_dates();
+
+        (0..days as i64)
+            .rev()
+            .map(|offset| {
+                let day = today - chrono::Duration::days(offset);
+                let count = completed_dates.iter().filter(|date| **date == day).count();
+                (day.format(label_format).to_string(), count as u64)
+            })
+            .collect()
+    }
+
+    /// Consecutive days, counting back from today, with at least one task or
+    /// subtask completed. A streak only breaks once a whole day passes with no
+    /// completions, so it still counts yesterday's run until today is over.
+    pub fn current_streak(&self) -> u32 {
+        let completed_dates = self.completion_dates();
+        let mut day = Local::now().date_naive();
+        if !completed_dates.contains(&day) {
+            day -= chrono::Duration::days(1);
+        }
+        let mut streak = 0;
+        while completed_dates.contains(&day) {
+            streak += 1;
+            day -= chrono::Duration::days(1);
+        }
+        streak
+    }
+
+    /// Local calendar day of every recorded completion.
+    fn completion_dates(&self) -> Vec<NaiveDate> {
+        self.tasks
+            .iter()
+            .flat_map(|task| std::iter::once(task).chain(task.sub_tasks.iter()))
+            .filter_map(|task| task.completed_at)
+            .map(|completed_at| completed_at.date_naive())
+            .collect()
+    }
+
+    pub fn cycle_theme(&mut self) {
+        let available_themes = self.theme_manager.get_available_themes();
+        if available_themes.is_empty() {
+            return;
+        }
+
+        let current_theme_name = &self.theme_manager.get_current_theme().name;
+
+        // Find current theme index by matching theme names
+        let current_index = available_themes
+            .iter()
+            .position(|theme_key| {
+                if let Some(theme) = self.theme_manager.get_theme_by_key(theme_key) {
+                    theme.name == *current_theme_name
+                } else {
+                    false
+                }
+            })
+            .unwrap_or(0);
+
+        let next_index = (current_index + 1) % available_themes.len();
+        let next_theme_name = &available_themes[next_index];
+
+        if self.theme_manager.set_theme(next_theme_name).is_err() {
+            // If setting fails, try to set to the first available theme
+            if let Some(first_theme) = available_themes.first() {
+                let _ = self.theme_manager.set_theme(first_theme);
+            }
+        }
+    }
+
+    pub fn get_available_theme_names(&self) -> Vec<String> {
+        self.theme_manager
             .get_available_themes()
             .iter()
             .filter_map(|key| {
@@ -592,3 +3710,1131 @@ impl App {
             .collect()
     }
 }
+
+/// Removes each matched date/time fragment from `input` (case-insensitively)
+/// and collapses the leftover whitespace.
+fn strip_fragments(input: &str, fragments: &[String]) -> String {
+    let mut cleaned = input.to_string();
+    for fragment in fragments {
+        if let Ok(re) = Regex::new(&format!("(?i){}", regex::escape(fragment))) {
+            cleaned = re.replace(&cleaned, "").into_owned();
+        }
+    }
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Drops `blocked_by` references to tasks that no longer exist.
+fn prune_dependencies(tasks: &mut [Task]) {
+    let ids: Vec<usize> = tasks.iter().map(|t| t.id).collect();
+    for task in tasks.iter_mut() {
+        task.blocked_by
+            .retain(|id| ids.contains(id) && *id != task.id);
+    }
+}
+
+/// An http(s) link: everything up to the next whitespace, less any closing
+/// punctuation that belongs to the sentence rather than the link.
+const URL_PATTERN: &str = r"https?://\S+";
+
+/// Byte ranges of the web links in `text`, in order.
+pub fn find_urls(text: &str) -> Vec<std::ops::Range<usize>> {
+    let Ok(re) = Regex::new(URL_PATTERN) else {
+        return Vec::new();
+    };
+    re.find_iter(text)
+        .map(|m| {
+            let url = m.as_str();
+            let mut end = url.len();
+            while let Some(c) = url[..end].chars().last() {
+                let unbalanced =
+                    c == ')' && url[..end].matches(')').count() > url[..end].matches('(').count();
+                if unbalanced || ".,;:!?'\"".contains(c) {
+                    end -= c.len_utf8();
+                } else {
+                    break;
+                }
+            }
+            m.start()..m.start() + end
+        })
+        .filter(|range| !text[range.clone()].ends_with("://"))
+        .collect()
+}
+
+/// Hands the URL to the platform's opener (`open`, `xdg-open`, or on
+/// Windows the URL handler run through `rundll32`) without waiting for the
+/// browser, and with its output kept off the screen. No shell is involved, so
+/// characters like `&` or `|` in a link can't run commands.
+fn open_url(url: &str) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the opener once it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// A `#tag` anywhere in the text: word characters (including non-ASCII
+/// letters) and hyphens, so "fix#work" and "#a,#b" both yield tags.
+const TAG_PATTERN: &str = r"#[\w-]+";
+
+/// Byte ranges of the tags in `text`, leaving out `#fragment`s of links.
+fn tag_ranges(text: &str) -> Vec<std::ops::Range<usize>> {
+    let Ok(re) = Regex::new(TAG_PATTERN) else {
+        return Vec::new();
+    };
+    let urls = find_urls(text);
+    re.find_iter(text)
+        .map(|m| m.range())
+        .filter(|tag| !urls.iter().any(|url| url.contains(&tag.start)))
+        .collect()
+}
+
+/// Every tag in the input, in order and without repeats.
+fn extract_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in tag_ranges(input)
+        .into_iter()
+        .map(|range| input[range].to_string())
+    {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Drops the tags from a description; they are stored separately and shown
+/// after it.
+fn strip_tags(description: &str) -> String {
+    let mut stripped = String::new();
+    let mut end = 0;
+    for tag in tag_ranges(description) {
+        stripped.push_str(&description[end..tag.start]);
+        stripped.push(' ');
+        end = tag.end;
+    }
+    stripped.push_str(&description[end..]);
+    stripped
+        .split_whitespace()
+        // Commas that only separated tags ("#a, #b") go with them
+        .filter(|word| !word.chars().all(|c| c == ','))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The fuzzy duplicate test: the same words in any order, or one word list
+/// appearing as a run inside the other.
+fn words_similar(a: &[String], b: &[String]) -> bool {
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if shorter.is_empty() {
+        return false;
+    }
+    let mut sorted_a = a.to_vec();
+    let mut sorted_b = b.to_vec();
+    sorted_a.sort();
+    sorted_b.sort();
+    sorted_a == sorted_b
+        || longer
+            .windows(shorter.len())
+            .any(|window| window == shorter)
+}
+
+/// Picks a new task's priority from urgency words in its description:
+/// "urgent" or "asap" make it high, "maybe" or "someday" low.
+fn infer_priority(input: &str) -> Priority {
+    let words: Vec<String> = input
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .collect();
+    let has = |keywords: &[&str]| words.iter().any(|word| keywords.contains(&word.as_str()));
+    if has(&["urgent", "asap", "critical"]) {
+        Priority::High
+    } else if has(&["maybe", "someday", "eventually"]) {
+        Priority::Low
+    } else {
+        Priority::Medium
+    }
+}
+
+/// Whether task input has something besides tags to describe the task.
+/// A lone date phrase counts, since it then names the task.
+fn has_description(input: &str) -> bool {
+    !strip_tags(input).trim().is_empty()
+}
+
+/// Splits a search query into lowercase terms on whitespace. Text inside
+/// double quotes is kept together as a single phrase term, as are the two
+/// words of `due:this week` and the like.
+pub fn search_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.to_lowercase().chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+
+    let mut merged: Vec<String> = Vec::with_capacity(terms.len());
+    for term in terms {
+        match merged.last_mut() {
+            Some(last) if matches!(last.as_str(), "due:this" | "due:next" | "due:last") => {
+                last.push(' ');
+                last.push_str(&term);
+            }
+            _ => merged.push(term),
+        }
+    }
+    merged
+}
+
+/// Whether a single lowercase search term matches any searchable field of the
+/// task or its subtasks. Priority and status keywords only match as whole terms.
+fn task_matches_term(task: &Task, term: &str, settings: &Settings) -> bool {
+    fields_match_term(task, term, settings)
+        // Filter by subtasks content
+        || task.sub_tasks.iter().any(|subtask| {
+            subtask.description.to_lowercase().contains(term)
+                || subtask.notes.as_ref().is_some_and(|notes| notes.to_lowercase().contains(term))
+                || subtask.tags.iter().any(|tag| tag.to_lowercase().contains(term))
+        })
+}
+
+/// Whether the task's own fields match the term, leaving out its subtasks.
+fn fields_match_term(task: &Task, term: &str, settings: &Settings) -> bool {
+    if let Some(value) = term.strip_prefix("due:") {
+        return due_matches(task, value.trim(), settings, Local::now());
+    }
+    if let Some(value) = term.strip_prefix("effort:") {
+        return match value.trim() {
+            "none" => task.effort.is_none(),
+            "any" => task.effort.is_some(),
+            value => Effort::parse(value).is_some_and(|effort| task.effort == Some(effort)),
+        };
+    }
+
+    // Filter by description (case-insensitive)
+    task.description.to_lowercase().contains(term)
+        // Filter by tags (case-insensitive)
+        || task.tags.iter().any(|tag| tag.to_lowercase().contains(term))
+        // Filter by priority
+        || match term {
+            "high" | "h" => matches!(task.priority, Priority::High),
+            "medium" | "med" | "m" => matches!(task.priority, Priority::Medium),
+            "low" | "l" => matches!(task.priority, Priority::Low),
+            _ => false,
+        }
+        // Filter by status
+        || match term {
+            "completed" | "done" | "finished" => task.is_done(),
+            "incomplete" | "pending" | "todo" => !task.is_done(),
+            "in-progress" | "started" | "doing" | "wip" => task.status == Status::InProgress,
+            _ => false,
+        }
+        // Filter by notes content
+        || task.notes.as_ref().is_some_and(|notes| notes.to_lowercase().contains(term))
+        // Filter by due date (if it exists)
+        || task.due_date.as_ref().is_some_and(|date| date.contains(term))
+}
+
+/// Matches a `due:` search term against the task's due date. The value is a
+/// keyword (`today`, `tomorrow`, `yesterday`, `overdue`, `none`, `this week`,
+/// `next week`, `last week`, `this month`, `next month`), an exact
+/// `YYYY-MM-DD`, `YYYY-MM` or `YYYY`, or anything the date parser reads as a
+/// single day, like `friday`. Names of ranges are resolved against `now`.
+fn due_matches(task: &Task, value: &str, settings: &Settings, now: DateTime<Local>) -> bool {
+    let deadline = task.due_date.as_deref().and_then(due_deadline);
+    match value {
+        "none" => return task.due_date.is_none(),
+        "overdue" => {
+            return !task.is_done() && deadline.is_some_and(|deadline| deadline < now.naive_local())
+        }
+        _ => {}
+    }
+    let Some(deadline) = deadline else {
+        return false;
+    };
+    match due_range(value, settings, now) {
+        Some((first, last)) => (first..=last).contains(&deadline.date()),
+        // Nothing we can read as a date; fall back to the plain text match
+        None => task
+            .due_date
+            .as_ref()
+            .is_some_and(|date| date.contains(value)),
+    }
+}
+
+/// The first and last day covered by a `due:` search value.
+fn due_range(
+    value: &str,
+    settings: &Settings,
+    now: DateTime<Local>,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let today = now.date_naive();
+    let week_start = settings.week_start;
+    let day = |date: NaiveDate| Some((date, date));
+    let month = |year: i32, month: u32| {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let next = if month == 12 {
+            NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+        } else {
+            NaiveDate::from_ymd_opt(year, month + 1, 1)?
+        };
+        Some((first, next.pred_opt()?))
+    };
+    let week =
+        |date: NaiveDate| Some((week_start.start_of_week(date), week_start.end_of_week(date)));
+
+    match value {
+        "today" => return day(today),
+        "tomorrow" => return day(today.succ_opt()?),
+        "yesterday" => return day(today.pred_opt()?),
+        "week" | "this week" => return week(today),
+        "next week" => return week(today + chrono::Duration::days(7)),
+        "last week" => return week(today - chrono::Duration::days(7)),
+        "month" | "this month" => return month(today.year(), today.month()),
+        "next month" => {
+            let (year, next) = if today.month() == 12 {
+                (today.year() + 1, 1)
+            } else {
+                (today.year(), today.month() + 1)
+            };
+            return month(year, next);
+        }
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return day(date);
+    }
+    if let Some((year, month_number)) = value.split_once('-') {
+        if let (Ok(year), Ok(month_number)) = (year.parse::<i32>(), month_number.parse::<u32>()) {
+            if year.to_string().len() == 4 {
+                return month(year, month_number);
+            }
+        }
+    }
+    if value.len() == 4 {
+        if let Ok(year) = value.parse::<i32>() {
+            return Some((
+                NaiveDate::from_ymd_opt(year, 1, 1)?,
+                NaiveDate::from_ymd_opt(year, 12, 31)?,
+            ));
+        }
+    }
+    // Only whole words, so a number like "15" still text-matches the date
+    if value.chars().any(|c| c.is_ascii_alphabetic()) {
+        if let Ok(date) = parse_date_string(value, now, settings.date_dialect.to_chrono()) {
+            return day(date.date_naive());
+        }
+    }
+    None
+}
+
+/// Largest margin that still leaves a usable viewport in a terminal of `size`.
+/// The horizontal margin is doubled when laid out, hence the wider divisor.
+pub fn max_margin(size: Rect) -> u16 {
+    const MIN_VIEWPORT_WIDTH: u16 = 40;
+    const MIN_VIEWPORT_HEIGHT: u16 = 10;
+    let by_height = size.height.saturating_sub(MIN_VIEWPORT_HEIGHT) / 2;
+    let by_width = size.width.saturating_sub(MIN_VIEWPORT_WIDTH) / 4;
+    by_height.min(by_width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WeekStart;
+    use crossterm::event::KeyCode;
+    use std::sync::Once;
+
+    /// A scratch directory for this test run, shared by every test.
+    fn test_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("todo-tests-{}", std::process::id()))
+    }
+
+    /// An app over an empty list. The config directory points at an empty
+    /// scratch directory, so the user's saved view, history and themes stay
+    /// out of the tests.
+    fn test_app() -> App {
+        static CONFIG_DIR: Once = Once::new();
+        CONFIG_DIR.call_once(|| std::env::set_var("TODO_CONFIG_DIR", test_dir()));
+        App::new_with_theme(
+            ThemeManager::new(),
+            Settings::default(),
+            test_dir().join("missing.json"),
+        )
+    }
+
+    /// Types `text` into the add popup and submits it, as a user would.
+    fn add(app: &mut App, text: &str) {
+        app.handle_action(Action::AddTask);
+        for c in text.chars() {
+            app.handle_action(Action::Input(c));
+        }
+        app.handle_action(Action::Submit);
+    }
+
+    /// A task as a tasks file would hold it, with defaults for every field
+    /// not given.
+    fn task(id: usize, description: &str, tags: &[&str]) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "description": description,
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    fn descriptions(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.description.as_str()).collect()
+    }
+
+    /// The description of the selected task, checking that the selection is
+    /// on a row that is actually shown.
+    fn selected_description(app: &App) -> Option<String> {
+        let selected = app.state.selected()?;
+        assert!(
+            selected < app.display_rows().len(),
+            "selection past the list"
+        );
+        let task_ref = app.selected_task_ref()?;
+        app.task(task_ref).map(|task| task.description.clone())
+    }
+
+    /// A fixed "now" for date parsing: Wednesday 14 October 2026, 10:00.
+    fn wednesday_morning() -> DateTime<Local> {
+        local_datetime(
+            NaiveDate::from_ymd_opt(2026, 10, 14)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+        )
+    }
+
+    fn parse(app: &App, input: &str) -> (String, Option<String>) {
+        app.extract_date_and_clean_description_at(input, wednesday_morning())
+    }
+
+    fn dated(description: &str, due_date: &str) -> (String, Option<String>) {
+        (description.to_string(), Some(due_date.to_string()))
+    }
+
+    fn undated(description: &str) -> (String, Option<String>) {
+        (description.to_string(), None)
+    }
+
+    #[test]
+    fn focus_mode_hides_completed_top_level_tasks() {
+        let mut app = test_app();
+        add(&mut app, "write report");
+        add(&mut app, "send invoice");
+        add(&mut app, "book flights");
+        app.tasks[1].status = Status::Done;
+
+        app.handle_action(Action::ToggleFocus);
+
+        assert_eq!(
+            descriptions(&app.get_displayed_tasks()),
+            ["write report", "book flights"]
+        );
+        assert_eq!(selected_description(&app).as_deref(), Some("write report"));
+    }
+
+    #[test]
+    fn completing_the_selected_task_in_focus_mode_keeps_a_valid_selection() {
+        let mut app = test_app();
+        add(&mut app, "write report");
+        add(&mut app, "send invoice");
+        app.handle_action(Action::ToggleFocus);
+        app.handle_action(Action::Next);
+        assert_eq!(selected_description(&app).as_deref(), Some("send invoice"));
+
+        // Todo, then in progress, then done
+        app.handle_action(Action::ToggleCompleted);
+        app.handle_action(Action::ToggleCompleted);
+
+        assert_eq!(selected_description(&app).as_deref(), Some("write report"));
+        app.handle_action(Action::ToggleCompleted);
+        app.handle_action(Action::ToggleCompleted);
+        assert_eq!(app.display_rows().len(), 0);
+        assert_eq!(app.state.selected(), None);
+    }
+
+    #[test]
+    fn iso_dates_are_parsed_and_stripped() {
+        let app = test_app();
+        assert_eq!(
+            parse(&app, "renew passport 2027-03-14"),
+            dated("renew passport", "2027-03-14")
+        );
+        assert_eq!(
+            parse(&app, "party on 2024-12-25"),
+            dated("party", "2024-12-25")
+        );
+        assert_eq!(
+            parse(&app, "meet 2026-10-20 at 3pm"),
+            dated("meet", "2026-10-20 15:00")
+        );
+    }
+
+    #[test]
+    fn month_name_dates_are_parsed_and_stripped() {
+        let app = test_app();
+        assert_eq!(
+            parse(&app, "send card dec 25"),
+            dated("send card", "2026-12-25")
+        );
+        assert_eq!(
+            parse(&app, "dec 25 send card"),
+            dated("send card", "2026-12-25")
+        );
+        assert_eq!(
+            parse(&app, "call mom 25 December 2027"),
+            dated("call mom", "2027-12-25")
+        );
+        assert_eq!(
+            parse(&app, "gift by 25th of dec"),
+            dated("gift", "2026-12-25")
+        );
+        // Already past this year, so next year's
+        assert_eq!(
+            parse(&app, "file taxes jan 5"),
+            dated("file taxes", "2027-01-05")
+        );
+    }
+
+    #[test]
+    fn numeric_dates_follow_the_dialect() {
+        let mut app = test_app();
+        assert_eq!(
+            parse(&app, "pay rent on 1/6"),
+            dated("pay rent", "2027-01-06")
+        );
+        assert_eq!(
+            parse(&app, "pay rent 3/4/2027"),
+            dated("pay rent", "2027-03-04")
+        );
+
+        app.settings.date_dialect = DateDialect::Uk;
+        assert_eq!(
+            parse(&app, "pay rent on 1/6"),
+            dated("pay rent", "2027-06-01")
+        );
+        assert_eq!(
+            parse(&app, "pay rent 3/4/2027"),
+            dated("pay rent", "2027-04-03")
+        );
+        assert_eq!(parse(&app, "gift by 25/12"), dated("gift", "2026-12-25"));
+    }
+
+    #[test]
+    fn fractions_are_not_dates() {
+        let app = test_app();
+        for input in [
+            "buy 1/2 cup flour",
+            "1/2 cup flour",
+            "fix bug 3/4 of the way",
+            "release v1/2/3",
+        ] {
+            assert_eq!(parse(&app, input), undated(input));
+        }
+    }
+
+    #[test]
+    fn one_six_flips_between_dialects() {
+        let mut app = test_app();
+        add(&mut app, "dentist on 1/6");
+        add(&mut app, "1/6/2027 plumber");
+        app.settings.date_dialect = DateDialect::Uk;
+        add(&mut app, "optician on 1/6");
+        add(&mut app, "1/6/2027 electrician");
+
+        let due: Vec<&str> = app
+            .tasks
+            .iter()
+            .map(|task| task.due_date.as_deref().unwrap_or_default())
+            .collect();
+        assert!(due[0].ends_with("-01-06"), "{}", due[0]);
+        assert_eq!(due[1], "2027-01-06");
+        assert!(due[2].ends_with("-06-01"), "{}", due[2]);
+        assert_eq!(due[3], "2027-06-01");
+    }
+
+    #[test]
+    fn help_names_the_active_dialect() {
+        let mut app = test_app();
+        app.settings.date_dialect = DateDialect::Uk;
+        app.handle_action(Action::ShowHelp);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 200)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("read as UK (day/month)"));
+    }
+
+    #[test]
+    fn incidental_numbers_are_not_times() {
+        let app = test_app();
+        for input in [
+            "fix bug 3:14 in pi",
+            "run a 5hour marathon",
+            "5h marathon training",
+            "read chapter 10",
+            "clean room 101",
+        ] {
+            assert_eq!(parse(&app, input), undated(input));
+        }
+    }
+
+    #[test]
+    fn times_need_am_pm_or_a_preposition() {
+        let app = test_app();
+        assert_eq!(
+            parse(&app, "dinner 7pm"),
+            dated("dinner", "2026-10-14 19:00")
+        );
+        assert_eq!(
+            parse(&app, "call client at 22:30"),
+            dated("call client", "2026-10-14 22:30")
+        );
+        assert_eq!(
+            parse(&app, "submit form by 17h"),
+            dated("submit form", "2026-10-14 17:00")
+        );
+        // Already past today, so tomorrow
+        assert_eq!(
+            parse(&app, "standup @ 9:15"),
+            dated("standup", "2026-10-15 09:15")
+        );
+        assert_eq!(
+            parse(&app, "review 10:30 am"),
+            dated("review", "2026-10-14 10:30")
+        );
+    }
+
+    #[test]
+    fn both_parsing_paths_strip_the_date_text() {
+        let app = test_app();
+        // chrono-english reads a leading date expression
+        assert_eq!(
+            parse(&app, "tomorrow at 3pm call mom"),
+            dated("call mom", "2026-10-15 15:00")
+        );
+        // The fallback parser finds one anywhere in the text
+        assert_eq!(
+            parse(&app, "call mom tomorrow at 3pm"),
+            dated("call mom", "2026-10-15 15:00")
+        );
+        assert_eq!(
+            parse(&app, "water plants on friday"),
+            dated("water plants", "2026-10-16 09:00")
+        );
+    }
+
+    #[test]
+    fn added_tasks_keep_only_the_description() {
+        let mut app = test_app();
+        add(&mut app, "call mom tomorrow at 3pm");
+        add(&mut app, "tomorrow buy bread");
+
+        assert_eq!(descriptions(&app.tasks), ["call mom", "buy bread"]);
+        assert!(app.tasks.iter().all(|task| task.due_date.is_some()));
+    }
+
+    #[test]
+    fn search_terms_must_all_match() {
+        let mut app = test_app();
+        app.tasks = vec![
+            task(1, "buy milk", &["#urgent"]),
+            task(2, "buy milk", &[]),
+            task(3, "urgent call", &[]),
+        ];
+        app.add_search_filters("milk urgent");
+        assert_eq!(descriptions(&app.get_displayed_tasks()), ["buy milk"]);
+        assert_eq!(app.get_displayed_tasks()[0].id, 1);
+    }
+
+    #[test]
+    fn keywords_apply_per_term() {
+        let mut app = test_app();
+        app.tasks = vec![task(1, "buy milk", &[]), task(2, "buy bread", &[])];
+        app.tasks[0].priority = Priority::High;
+        app.tasks[1].status = Status::Done;
+
+        app.add_search_filters("buy high");
+        assert_eq!(descriptions(&app.get_displayed_tasks()), ["buy milk"]);
+        app.filters.clear();
+        app.add_search_filters("buy done");
+        assert_eq!(descriptions(&app.get_displayed_tasks()), ["buy bread"]);
+    }
+
+    #[test]
+    fn quotes_keep_a_phrase_together() {
+        assert_eq!(
+            search_terms("\"buy milk\" #urgent"),
+            ["buy milk", "#urgent"]
+        );
+
+        let mut app = test_app();
+        app.tasks = vec![task(1, "buy milk", &[]), task(2, "milk to buy", &[])];
+        app.add_search_filters("\"buy milk\"");
+        assert_eq!(descriptions(&app.get_displayed_tasks()), ["buy milk"]);
+    }
+
+    #[test]
+    fn tags_are_stripped_from_added_descriptions() {
+        let mut app = test_app();
+        add(&mut app, "buy milk #shopping");
+        add(&mut app, "call #work mom about #family-dinner");
+        assert_eq!(app.tasks[0].description, "buy milk");
+        assert_eq!(app.tasks[0].tags, ["#shopping"]);
+        assert_eq!(app.tasks[1].description, "call mom about");
+        assert_eq!(app.tasks[1].tags, ["#work", "#family-dinner"]);
+
+        app.select_first();
+        app.handle_action(Action::AddSubtask);
+        for c in "pick up oats #errand".chars() {
+            app.handle_action(Action::Input(c));
+        }
+        app.handle_action(Action::Submit);
+        let sub_task = &app.tasks[0].sub_tasks[0];
+        assert_eq!(sub_task.description, "pick up oats");
+        assert_eq!(sub_task.tags, ["#errand"]);
+    }
+
+    #[test]
+    fn adjacent_tags_are_separate() {
+        assert_eq!(extract_tags("plan #trip#beach"), ["#trip", "#beach"]);
+        assert_eq!(extract_tags("urgent#work"), ["#work"]);
+    }
+
+    #[test]
+    fn punctuation_ends_a_tag() {
+        assert_eq!(extract_tags("#a,#b, #c; (#d)."), ["#a", "#b", "#c", "#d"]);
+        assert_eq!(strip_tags("pack #a, #b"), "pack");
+        assert_eq!(extract_tags("#a #a"), ["#a"]);
+    }
+
+    #[test]
+    fn tags_keep_hyphens_and_letters_beyond_ascii() {
+        assert_eq!(
+            extract_tags("#follow-up #café #日本"),
+            ["#follow-up", "#café", "#日本"]
+        );
+        assert!(extract_tags("see https://example.com/page#section").is_empty());
+    }
+
+    #[test]
+    fn a_lone_date_word_is_a_plain_task() {
+        let mut app = test_app();
+        for name in ["May", "Monday", "tomorrow", "March 3"] {
+            add(&mut app, name);
+        }
+        assert_eq!(
+            descriptions(&app.tasks),
+            ["May", "Monday", "tomorrow", "March 3"]
+        );
+        assert!(app.tasks.iter().all(|task| task.due_date.is_none()));
+
+        add(&mut app, "call mom monday");
+        let dated = app.tasks.last().unwrap();
+        assert_eq!(dated.description, "call mom");
+        assert!(dated.due_date.is_some());
+    }
+
+    #[test]
+    fn next_monday_follows_the_week_start() {
+        let sunday = local_datetime(
+            NaiveDate::from_ymd_opt(2026, 10, 18)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+        );
+        let mut app = test_app();
+        let next_monday =
+            |app: &App| app.extract_date_and_clean_description_at("call mom next monday", sunday);
+        assert_eq!(next_monday(&app), dated("call mom", "2026-10-19 09:00"));
+        app.settings.week_start = WeekStart::Sunday;
+        assert_eq!(next_monday(&app), dated("call mom", "2026-10-26 09:00"));
+
+        // A plain weekday is the next one to come, whatever the week start
+        assert_eq!(
+            app.extract_date_and_clean_description_at("call mom monday", sunday),
+            dated("call mom", "2026-10-19 09:00")
+        );
+    }
+
+    /// Tasks due at each of the given times, matched against `value` on a
+    /// Wednesday morning; returns the due dates that match.
+    fn due_search(value: &str, settings: &Settings, due_dates: &[&str]) -> Vec<String> {
+        due_dates
+            .iter()
+            .map(|due| {
+                let mut task = task(1, "due", &[]);
+                task.due_date = Some(due.to_string());
+                task
+            })
+            .filter(|task| due_matches(task, value, settings, wednesday_morning()))
+            .filter_map(|task| task.due_date)
+            .collect()
+    }
+
+    #[test]
+    fn due_today_covers_the_whole_day() {
+        let settings = Settings::default();
+        let dates = [
+            "2026-10-13 23:00",
+            "2026-10-14",
+            "2026-10-14 08:00",
+            "2026-10-14 22:00",
+            "2026-10-15",
+        ];
+        assert_eq!(
+            due_search("today", &settings, &dates),
+            ["2026-10-14", "2026-10-14 08:00", "2026-10-14 22:00"]
+        );
+    }
+
+    #[test]
+    fn due_overdue_is_past_and_still_open() {
+        let settings = Settings::default();
+        // Date-only deadlines last until the end of the day
+        let dates = [
+            "2026-10-13",
+            "2026-10-14 08:00",
+            "2026-10-14",
+            "2026-10-14 12:00",
+        ];
+        assert_eq!(
+            due_search("overdue", &settings, &dates),
+            ["2026-10-13", "2026-10-14 08:00"]
+        );
+
+        let mut done = task(1, "done", &[]);
+        done.due_date = Some("2026-10-13".to_string());
+        done.status = Status::Done;
+        assert!(!due_matches(
+            &done,
+            "overdue",
+            &settings,
+            wednesday_morning()
+        ));
+    }
+
+    #[test]
+    fn due_month_covers_every_day_of_it() {
+        let settings = Settings::default();
+        let dates = ["2024-05-31", "2024-06-01", "2024-06-30 23:30", "2024-07-01"];
+        assert_eq!(
+            due_search("2024-06", &settings, &dates),
+            ["2024-06-01", "2024-06-30 23:30"]
+        );
+    }
+
+    #[test]
+    fn due_this_week_follows_the_week_start() {
+        let mut settings = Settings::default();
+        let dates = ["2026-10-11", "2026-10-12", "2026-10-17", "2026-10-18"];
+        assert_eq!(
+            due_search("this week", &settings, &dates),
+            ["2026-10-12", "2026-10-17", "2026-10-18"]
+        );
+        settings.week_start = WeekStart::Sunday;
+        assert_eq!(
+            due_search("this week", &settings, &dates),
+            ["2026-10-11", "2026-10-12", "2026-10-17"]
+        );
+    }
+
+    #[test]
+    fn blank_input_adds_no_task() {
+        let mut app = test_app();
+        for input in ["", "   ", "#work #home"] {
+            add(&mut app, input);
+            assert!(app.tasks.is_empty(), "{:?} added a task", input);
+            // The popup stays open to type a description
+            assert!(matches!(app.mode, AppMode::Insert));
+            app.handle_action(Action::Cancel);
+        }
+
+        add(&mut app, "real task");
+        app.select_first();
+        app.handle_action(Action::AddSubtask);
+        for c in "  #tag ".chars() {
+            app.handle_action(Action::Input(c));
+        }
+        app.handle_action(Action::Submit);
+        assert!(app.tasks[0].sub_tasks.is_empty());
+    }
+
+    fn links(text: &str) -> Vec<&str> {
+        find_urls(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn links_stop_before_sentence_punctuation() {
+        assert_eq!(
+            links("read https://example.com/a, then http://example.org/b."),
+            ["https://example.com/a", "http://example.org/b"]
+        );
+        assert_eq!(
+            links("(see https://example.com/x)"),
+            ["https://example.com/x"]
+        );
+        assert_eq!(
+            links("https://en.wikipedia.org/wiki/Rust_(language)"),
+            ["https://en.wikipedia.org/wiki/Rust_(language)"]
+        );
+    }
+
+    #[test]
+    fn links_keep_queries_and_fragments() {
+        assert_eq!(
+            links("https://example.com/search?q=a&b=c#top!"),
+            ["https://example.com/search?q=a&b=c#top"]
+        );
+        assert!(links("just http:// and ftp://example.com").is_empty());
+    }
+
+    #[test]
+    fn completing_a_recurring_task_adds_the_next_occurrence() {
+        let mut app = test_app();
+        app.tasks = vec![task(1, "water plants", &["#home"])];
+        app.tasks[0].due_date = Some("2026-10-14 09:00".to_string());
+        app.next_id = 2;
+        app.select_first();
+        app.handle_action(Action::SetRecurrence);
+        for c in "daily".chars() {
+            app.handle_action(Action::Input(c));
+        }
+        app.handle_action(Action::Submit);
+        assert_eq!(app.tasks[0].recurrence, Some(Recurrence::Daily));
+
+        // Todo -> in progress -> done
+        app.handle_action(Action::ToggleCompleted);
+        app.handle_action(Action::ToggleCompleted);
+        assert_eq!(app.tasks.len(), 2);
+        let (done, next) = (&app.tasks[0], &app.tasks[1]);
+        assert!(done.is_done());
+        assert_eq!(done.recurrence, None);
+        assert_eq!(next.description, "water plants");
+        assert_eq!(next.tags, ["#home"]);
+        assert_eq!(next.status, Status::Todo);
+        assert_eq!(next.due_date.as_deref(), Some("2026-10-15 09:00"));
+        assert_eq!(next.recurrence, Some(Recurrence::Daily));
+        assert_ne!(next.id, done.id);
+
+        // Reopening and completing the old one again adds nothing
+        app.select_first();
+        app.handle_action(Action::ToggleCompleted);
+        app.handle_action(Action::ToggleCompleted);
+        app.handle_action(Action::ToggleCompleted);
+        assert_eq!(app.tasks.len(), 2);
+    }
+
+    #[test]
+    fn pasted_control_characters_are_cleaned_up() {
+        let mut app = test_app();
+        app.handle_action(Action::AddTask);
+        app.handle_action(Action::Paste(
+            "fix\tthe\r\nlogin\u{7} bug\n\n  asap-ish\u{1b}".to_string(),
+        ));
+        app.handle_action(Action::Submit);
+        assert_eq!(descriptions(&app.tasks), ["fix the login bug asap-ish"]);
+    }
+
+    #[test]
+    fn typed_control_characters_are_cleaned_up() {
+        let mut app = test_app();
+        app.handle_action(Action::AddTask);
+        for c in "call\tbob\u{0}\u{8}".chars() {
+            app.handle_action(Action::Input(c));
+        }
+        app.handle_action(Action::Submit);
+        assert_eq!(descriptions(&app.tasks), ["call bob"]);
+
+        // Search and date inputs get the same treatment
+        app.handle_action(Action::Search);
+        app.handle_action(Action::Paste("bob\nsmith".to_string()));
+        assert_eq!(app.search_input, "bob smith");
+    }
+
+    #[test]
+    fn no_key_is_bound_twice_without_a_condition() {
+        let mut seen = Vec::new();
+        for (_, bindings) in crate::action::bindings() {
+            for binding in bindings.iter().filter(|binding| binding.when.is_none()) {
+                for key in binding.keys {
+                    assert!(!seen.contains(key), "{:?} is bound twice", key);
+                    seen.push(*key);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn keys_translate_through_the_binding_table() {
+        let mut app = test_app();
+        let key = |app: &App, code| crate::action::normal_mode_action(app, code);
+        assert_eq!(key(&app, KeyCode::Char('e')), Some(Action::SetEffort));
+        assert_eq!(key(&app, KeyCode::F(1)), Some(Action::ShowHelp));
+        assert_eq!(key(&app, KeyCode::Char('3')), Some(Action::RemoveFilter(2)));
+        assert_eq!(key(&app, KeyCode::Char('Q')), None);
+        assert_eq!(key(&app, KeyCode::Esc), None);
+
+        app.filters.push(ActiveFilter::Search("milk".to_string()));
+        assert_eq!(key(&app, KeyCode::Esc), Some(Action::ClearSearch));
+    }
+
+    #[test]
+    fn the_help_shows_the_bound_keys() {
+        let app = test_app();
+        let sections = crate::action::help_sections();
+        let listed: Vec<&Action> = sections.iter().flat_map(|(_, actions)| actions).collect();
+        assert!(listed.contains(&&Action::SetEffort));
+        for action in listed {
+            assert!(action.help_key(&app).is_some(), "{:?} has no key", action);
+        }
+        assert_eq!(Action::SetEffort.help_key(&app).as_deref(), Some("e"));
+        assert_eq!(Action::ShowHelp.key_hint().as_deref(), Some("h / F1"));
+        assert_eq!(Action::RemoveFilter(4).key_hint().as_deref(), Some("1-9"));
+        assert_eq!(Action::EditTheme.key_hint(), None);
+    }
+
+    /// An app sorted by due date over tasks due as given, in this order.
+    fn sorted_by_due(dues: &[(&str, Option<&str>)], date_only_sort_time: NaiveTime) -> App {
+        let mut app = test_app();
+        app.settings.date_only_sort_time = date_only_sort_time;
+        app.tasks = dues
+            .iter()
+            .enumerate()
+            .map(|(index, (description, due))| Task {
+                due_date: due.map(str::to_string),
+                ..task(index + 1, description, &[])
+            })
+            .collect();
+        app.sort_mode = SortMode::Due;
+        app
+    }
+
+    #[test]
+    fn due_sort_puts_timed_tasks_at_their_time_and_undated_last() {
+        let dues = [
+            ("someday", None),
+            ("evening", Some("2024-06-01 20:00")),
+            ("next day", Some("2024-06-02")),
+            ("that day", Some("2024-06-01")),
+            ("morning", Some("2024-06-01 08:00")),
+            ("earlier", Some("2024-05-31 23:30")),
+        ];
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let app = sorted_by_due(&dues, noon);
+        assert_eq!(
+            descriptions(&app.get_displayed_tasks()),
+            ["earlier", "morning", "that day", "evening", "next day", "someday"]
+        );
+    }
+
+    #[test]
+    fn date_only_tasks_sort_at_the_configured_time() {
+        let dues = [
+            ("that day", Some("2024-06-01")),
+            ("morning", Some("2024-06-01 08:00")),
+        ];
+        let early = NaiveTime::from_hms_opt(6, 0, 0).unwrap();
+        let app = sorted_by_due(&dues, early);
+        assert_eq!(
+            descriptions(&app.get_displayed_tasks()),
+            ["that day", "morning"]
+        );
+
+        let late = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+        let app = sorted_by_due(&dues, late);
+        assert_eq!(
+            descriptions(&app.get_displayed_tasks()),
+            ["morning", "that day"]
+        );
+    }
+
+    #[test]
+    fn due_sort_keeps_the_list_order_for_equal_dates() {
+        let dues = [
+            ("undated b", None),
+            ("same c", Some("2024-06-01 08:00")),
+            ("undated a", None),
+            ("same a", Some("2024-06-01 08:00")),
+            ("same b", Some("2024-06-01")),
+        ];
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let app = sorted_by_due(&dues, eight);
+        assert_eq!(
+            descriptions(&app.get_displayed_tasks()),
+            ["same c", "same a", "same b", "undated b", "undated a"]
+        );
+    }
+
+    #[test]
+    fn a_failed_save_on_quit_keeps_the_app_open_once() {
+        let mut app = test_app();
+        app.tasks_path = test_dir().join("no-such-dir").join("tasks.json");
+        add(&mut app, "buy milk");
+        // An earlier autosave failure doesn't count as a failed quit
+        app.save_error = Some("Failed to save".to_string());
+
+        app.handle_action(Action::Quit);
+        assert!(!app.should_quit);
+        assert!(app.save_error.is_some());
+
+        app.handle_action(Action::Quit);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn quit_exits_when_its_save_succeeds() {
+        let mut app = test_app();
+        std::fs::create_dir_all(test_dir()).unwrap();
+        app.tasks_path = test_dir().join("quit.json");
+        add(&mut app, "buy milk");
+        app.save_error = Some("Failed to save".to_string());
+
+        app.handle_action(Action::Quit);
+        assert!(app.should_quit);
+        assert!(app.save_error.is_none());
+    }
+}
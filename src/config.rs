@@ -0,0 +1,715 @@
+use crate::app::SortMode;
+use crate::task::Priority;
+use crate::theme::SerializableColor;
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use chrono_english::Dialect;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How ambiguous numeric dates such as "1/6" are read.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DateDialect {
+    /// Month first: "1/6" is January 6th
+    Us,
+    /// Day first: "1/6" is the 1st of June
+    Uk,
+}
+
+impl DateDialect {
+    pub fn label(self) -> &'static str {
+        match self {
+            DateDialect::Us => "US (month/day)",
+            DateDialect::Uk => "UK (day/month)",
+        }
+    }
+
+    pub fn to_chrono(self) -> Dialect {
+        match self {
+            DateDialect::Us => Dialect::Us,
+            DateDialect::Uk => Dialect::Uk,
+        }
+    }
+}
+
+/// The day weeks begin on, which decides where "this week" ends.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// The first day of the week `date` falls in.
+    pub fn start_of_week(self, date: NaiveDate) -> NaiveDate {
+        let offset = match self {
+            WeekStart::Monday => date.weekday().num_days_from_monday(),
+            WeekStart::Sunday => date.weekday().num_days_from_sunday(),
+        };
+        date - chrono::Duration::days(offset as i64)
+    }
+
+    /// The last day of the week `date` falls in.
+    pub fn end_of_week(self, date: NaiveDate) -> NaiveDate {
+        self.start_of_week(date) + chrono::Duration::days(6)
+    }
+}
+
+/// Clock style used when displaying due times; storage is always 24-hour.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// Glyphs used when rendering the task list. Any symbol left out of the
+/// settings file keeps its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Symbols {
+    pub bullet: String,
+    pub check: String,
+    pub in_progress: String,
+    pub subtask: String,
+    pub highlight: String,
+    pub priority_high: String,
+    pub priority_medium: String,
+    pub priority_low: String,
+    pub pinned: String,
+    pub blocked: String,
+    pub notes: String,
+    pub streak: String,
+    pub timer: String,
+    pub collapsed: String,
+    pub someday: String,
+    pub recurring: String,
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Symbols {
+            bullet: "❯".to_string(),
+            check: "✔".to_string(),
+            in_progress: "◐".to_string(),
+            subtask: "↳".to_string(),
+            highlight: "➤".to_string(),
+            priority_high: "▲".to_string(),
+            priority_medium: "●".to_string(),
+            priority_low: "▼".to_string(),
+            pinned: "📌".to_string(),
+            blocked: "🔒".to_string(),
+            notes: "📄".to_string(),
+            streak: "🔥".to_string(),
+            timer: "⏱".to_string(),
+            collapsed: "▸".to_string(),
+            someday: "☁".to_string(),
+            recurring: "↻".to_string(),
+        }
+    }
+}
+
+impl Symbols {
+    /// Plain ASCII set for terminals or fonts without unicode glyphs.
+    pub fn ascii() -> Self {
+        Symbols {
+            bullet: ">".to_string(),
+            check: "x".to_string(),
+            in_progress: "o".to_string(),
+            subtask: "`-".to_string(),
+            highlight: ">>".to_string(),
+            priority_high: "^".to_string(),
+            priority_medium: "-".to_string(),
+            priority_low: "v".to_string(),
+            pinned: "*".to_string(),
+            blocked: "!".to_string(),
+            notes: "[n]".to_string(),
+            streak: "~".to_string(),
+            timer: "@".to_string(),
+            collapsed: "+".to_string(),
+            someday: "~".to_string(),
+            recurring: "(r)".to_string(),
+        }
+    }
+}
+
+/// How closely a new task must match an open one before adding it asks for
+/// confirmation. Descriptions are compared lowercased, without tags or dates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateCheck {
+    Off,
+    /// The same words in the same order
+    Exact,
+    /// Also the same words in any order, or one description containing the
+    /// other
+    Fuzzy,
+}
+
+/// Whether changing a parent's status carries over to its subtasks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CascadeCompletion {
+    /// Parents and subtasks are completed independently
+    Off,
+    /// Completing a parent completes its open subtasks
+    Complete,
+    /// As `Complete`, and reopening the parent reopens its subtasks
+    CompleteAndReopen,
+}
+
+/// What loading does with open recurring tasks whose due date has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdueRecurrence {
+    /// They stay overdue until completed
+    Off,
+    /// The due date moves on by one occurrence, which may still be past
+    CatchUp,
+    /// The due date moves on to the first occurrence not yet past
+    Skip,
+}
+
+/// Whether a completion gauge is drawn above the footer, and what it counts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressBar {
+    Off,
+    /// Completed top-level tasks out of all of them
+    TopLevel,
+    /// Subtasks counted alongside their parents
+    WithSubtasks,
+}
+
+/// Which destructive actions ask for confirmation first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Confirmations {
+    /// Deleting a task or subtask with no subtasks of its own
+    pub delete_task: bool,
+    /// Deleting a task along with its subtasks; checked instead of
+    /// `delete_task` since it loses more
+    pub delete_with_subtasks: bool,
+    pub clear_completed: bool,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Confirmations {
+            delete_task: true,
+            delete_with_subtasks: true,
+            clear_completed: true,
+        }
+    }
+}
+
+/// How completed tasks are drawn in the list. The defaults strike them
+/// through in the theme's `surface2` color.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletedStyle {
+    pub strikethrough: bool,
+    pub dim: bool,
+    /// Overrides the theme color, e.g. `{"r": 120, "g": 120, "b": 120}`
+    pub color: Option<SerializableColor>,
+}
+
+impl Default for CompletedStyle {
+    fn default() -> Self {
+        CompletedStyle {
+            strikethrough: true,
+            dim: false,
+            color: None,
+        }
+    }
+}
+
+/// Due dates given to new tasks entered without one, as date phrases such as
+/// "today" or "tomorrow 5pm". A priority left unset gets no default, and all
+/// are unset unless configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefaultDue {
+    pub high: Option<String>,
+    pub medium: Option<String>,
+    pub low: Option<String>,
+}
+
+impl DefaultDue {
+    pub fn for_priority(&self, priority: &Priority) -> Option<&str> {
+        match priority {
+            Priority::High => self.high.as_deref(),
+            Priority::Medium => self.medium.as_deref(),
+            Priority::Low => self.low.as_deref(),
+        }
+    }
+}
+
+/// User settings read from `settings.json` in the config directory. Every
+/// field is optional in the file and falls back to its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub date_dialect: DateDialect,
+    /// Time used for "tomorrow" and weekdays when no time is given
+    pub default_morning_time: NaiveTime,
+    /// Time used for "today" when no time is given
+    pub default_end_of_day_time: NaiveTime,
+    /// Where a due date without a time sorts among timed ones on that day
+    pub date_only_sort_time: NaiveTime,
+    pub time_format: TimeFormat,
+    pub week_start: WeekStart,
+    /// Due dates closer than this are highlighted as coming up soon
+    pub due_soon_hours: u32,
+    /// Open tasks due more than this many days out are shown muted as
+    /// "someday" items; never when unset
+    pub someday_days: Option<u32>,
+    /// Blend due date colors from red to green by how far off they are,
+    /// instead of the overdue/soon/upcoming colors
+    pub due_gradient: bool,
+    pub symbols: Symbols,
+    /// Longest task description accepted, in characters; unlimited when unset
+    pub max_input_length: Option<usize>,
+    /// Forces ASCII rendering on or off; detected from the locale when unset
+    pub ascii: Option<bool>,
+    pub confirm: Confirmations,
+    pub completed_style: CompletedStyle,
+    pub default_due: DefaultDue,
+    pub duplicate_check: DuplicateCheck,
+    pub cascade_completion: CascadeCompletion,
+    pub overdue_recurrence: OverdueRecurrence,
+    pub progress_bar: ProgressBar,
+    /// Tags shown on a row that would overflow the list width before the rest
+    /// are summarised as "+K more"
+    pub max_tags: usize,
+    /// Show the priority and due date in a column at the right edge of the
+    /// list instead of after each description
+    pub due_column: bool,
+    /// Tags added to every new task, with or without the leading '#'
+    pub default_tags: Vec<String>,
+    /// More default tags for one tasks file, keyed by its name without the
+    /// extension ("work" for `work.json`)
+    pub list_tags: HashMap<String, Vec<String>>,
+    /// Say how many tasks are overdue or due today when the app opens
+    pub startup_summary: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            date_dialect: DateDialect::Us,
+            default_morning_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            default_end_of_day_time: NaiveTime::from_hms_opt(23, 59, 0).unwrap(),
+            date_only_sort_time: NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+            time_format: TimeFormat::TwentyFourHour,
+            week_start: WeekStart::Monday,
+            due_soon_hours: 24,
+            someday_days: Some(90),
+            due_gradient: false,
+            symbols: Symbols::default(),
+            max_input_length: None,
+            ascii: None,
+            confirm: Confirmations::default(),
+            completed_style: CompletedStyle::default(),
+            default_due: DefaultDue::default(),
+            duplicate_check: DuplicateCheck::Exact,
+            cascade_completion: CascadeCompletion::Off,
+            overdue_recurrence: OverdueRecurrence::Off,
+            progress_bar: ProgressBar::TopLevel,
+            max_tags: 3,
+            due_column: false,
+            default_tags: Vec::new(),
+            list_tags: HashMap::new(),
+            startup_summary: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings file, falling back to defaults when it is missing
+    /// or unreadable.
+    pub fn load() -> Self {
+        let Ok(path) = settings_path() else {
+            return Settings::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("Warning: Failed to parse settings file {:?}: {}", path, e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    /// The default tags for new tasks in the tasks file at `path`: the global
+    /// ones followed by that file's own, each starting with '#'.
+    pub fn default_tags_for(&self, path: &Path) -> Vec<String> {
+        let list = path
+            .file_stem()
+            .and_then(|stem| self.list_tags.get(stem.to_string_lossy().as_ref()));
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.default_tags.iter().chain(list.into_iter().flatten()) {
+            let tag = tag.trim().trim_start_matches('#');
+            if tag.is_empty() {
+                continue;
+            }
+            let tag = format!("#{}", tag);
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Decides whether to render in ASCII, preferring the `--ascii` flag,
+    /// then the settings file, then what the environment suggests. In ASCII
+    /// mode any custom symbols are replaced by the ASCII set.
+    pub fn resolve_ascii(&mut self, forced: bool) {
+        let ascii = forced || self.ascii.unwrap_or_else(terminal_lacks_unicode);
+        if ascii {
+            self.symbols = Symbols::ascii();
+        }
+        self.ascii = Some(ascii);
+    }
+}
+
+/// Guesses from `LC_ALL`/`LC_CTYPE`/`LANG` and `TERM` whether the terminal can
+/// show unicode. An unset locale is given the benefit of the doubt.
+fn terminal_lacks_unicode() -> bool {
+    let term = std::env::var("TERM").unwrap_or_default();
+    if term == "dumb" || term == "linux" || term.starts_with("vt") {
+        return true;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        }
+        None => false,
+    }
+}
+
+/// Path of the named template in the `templates` config directory.
+pub fn template_path(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config_dir()?
+        .join("templates")
+        .join(format!("{}.json", name)))
+}
+
+/// How much blank space separates rows in the task list.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    /// Every row directly below the previous one
+    #[default]
+    Compact,
+    /// A blank line between top-level tasks
+    Comfortable,
+    /// A blank line before subtasks too
+    Spacious,
+}
+
+impl Density {
+    pub fn next(self) -> Self {
+        match self {
+            Density::Compact => Density::Comfortable,
+            Density::Comfortable => Density::Spacious,
+            Density::Spacious => Density::Compact,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Comfortable => "comfortable",
+            Density::Spacious => "spacious",
+        }
+    }
+}
+
+/// View preferences remembered between runs. Kept in `state.json` rather than
+/// the settings file so that saving them never rewrites the user's settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub margin: u16,
+    pub density: Density,
+    /// List only the matching subtasks under a search result's parent
+    pub subtask_results: bool,
+    /// The view toggles as they were last left, saved on quit
+    pub focus: bool,
+    pub hide_completed: bool,
+    pub group_completed: bool,
+    pub sort: SortMode,
+    pub line_numbers: bool,
+    pub details: bool,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        UiState {
+            margin: 1,
+            density: Density::Compact,
+            subtask_results: false,
+            focus: false,
+            hide_completed: false,
+            group_completed: false,
+            sort: SortMode::Manual,
+            line_numbers: false,
+            details: false,
+        }
+    }
+}
+
+impl UiState {
+    pub fn load() -> Self {
+        ui_state_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = ui_state_path().map_err(|e| io::Error::other(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn ui_state_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config_dir()?.join("state.json"))
+}
+
+/// Recently submitted searches, oldest first. Missing or unreadable history
+/// just starts empty.
+pub fn load_search_history() -> Vec<String> {
+    search_history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_search_history(history: &[String]) -> io::Result<()> {
+    let path = search_history_path().map_err(|e| io::Error::other(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(history)?)
+}
+
+fn search_history_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config_dir()?.join("search_history.json"))
+}
+
+/// A named set of list filters, saved to `filter_presets.json` so a search
+/// that is used all the time can be applied in one go.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    /// Search terms as typed in the search bar, phrases in double quotes
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub hide_completed: bool,
+    #[serde(default)]
+    pub focus: bool,
+}
+
+pub fn load_filter_presets() -> Vec<FilterPreset> {
+    filter_presets_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_filter_presets(presets: &[FilterPreset]) -> io::Result<()> {
+    let path = filter_presets_path().map_err(|e| io::Error::other(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(presets)?)
+}
+
+fn filter_presets_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config_dir()?.join("filter_presets.json"))
+}
+
+fn settings_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(config_dir()?.join("settings.json"))
+}
+
+/// Environment variable naming the directory to keep everything in: the
+/// settings, state and themes, and the tasks file unless `--file` is given.
+const CONFIG_DIR_VAR: &str = "TODO_CONFIG_DIR";
+
+/// Where the config files live: `$TODO_CONFIG_DIR`, then `todo` inside
+/// `$XDG_CONFIG_HOME` (honoured on every platform), then the platform config
+/// directory, then `~/.config/todo`. Relative or empty values are ignored.
+pub fn config_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    config_dir_in(&|var| std::env::var_os(var))
+}
+
+/// `config_dir` with the environment read through `env`, so tests can supply
+/// their own variables.
+fn config_dir_in(env: &Env) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(dir) = env_dir(env, CONFIG_DIR_VAR) {
+        return Ok(dir);
+    }
+    if let Some(xdg) = env_dir(env, "XDG_CONFIG_HOME") {
+        return Ok(xdg.join("todo"));
+    }
+    if let Some(config_dir) = dirs::config_dir() {
+        Ok(config_dir.join("todo"))
+    } else {
+        // Fallback to home directory
+        if let Some(home_dir) = dirs::home_dir() {
+            Ok(home_dir.join(".config").join("todo"))
+        } else {
+            Err("Could not determine config directory".into())
+        }
+    }
+}
+
+/// The tasks file used without `--file`: `tasks.json` in the config
+/// directory, found the same way as `config_dir`, so everything lives
+/// together. For older setups a `tasks.json` already in the current
+/// directory is still used, unless `$TODO_CONFIG_DIR` is set.
+pub fn default_tasks_path() -> PathBuf {
+    default_tasks_path_in(
+        &|var| std::env::var_os(var),
+        Path::new(LEGACY_TASKS_FILE).exists(),
+    )
+}
+
+/// Where tasks were kept before they moved into the config directory.
+const LEGACY_TASKS_FILE: &str = "tasks.json";
+
+/// `default_tasks_path` with the environment read through `env`;
+/// `legacy_exists` says whether the current directory holds a tasks file.
+fn default_tasks_path_in(env: &Env, legacy_exists: bool) -> PathBuf {
+    let legacy = PathBuf::from(LEGACY_TASKS_FILE);
+    if legacy_exists && env_dir(env, CONFIG_DIR_VAR).is_none() {
+        return legacy;
+    }
+    config_dir_in(env)
+        .map(|dir| dir.join("tasks.json"))
+        .unwrap_or(legacy)
+}
+
+/// Looks up an environment variable.
+type Env = dyn Fn(&str) -> Option<OsString>;
+
+fn env_dir(env: &Env, var: &str) -> Option<PathBuf> {
+    env(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_dialect_defaults_to_us_and_reads_uk_from_settings() {
+        assert_eq!(Settings::default().date_dialect, DateDialect::Us);
+        let settings: Settings = serde_json::from_str(r#"{"date_dialect": "uk"}"#).unwrap();
+        assert_eq!(settings.date_dialect, DateDialect::Uk);
+        assert_eq!(settings.date_dialect.to_chrono(), Dialect::Uk);
+    }
+
+    /// An environment holding only `vars`.
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), OsString::from(value)))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn config_dir_prefers_the_override_then_xdg() {
+        let both = env(&[
+            ("TODO_CONFIG_DIR", "/srv/todo"),
+            ("XDG_CONFIG_HOME", "/xdg"),
+        ]);
+        assert_eq!(config_dir_in(&both).unwrap(), PathBuf::from("/srv/todo"));
+        let xdg = env(&[("XDG_CONFIG_HOME", "/xdg")]);
+        assert_eq!(config_dir_in(&xdg).unwrap(), PathBuf::from("/xdg/todo"));
+    }
+
+    #[test]
+    fn relative_or_empty_dirs_are_ignored() {
+        let relative = env(&[("TODO_CONFIG_DIR", "todo"), ("XDG_CONFIG_HOME", "")]);
+        let dir = config_dir_in(&relative).unwrap();
+        assert!(dir.is_absolute() && dir.ends_with("todo"), "{:?}", dir);
+        assert_eq!(
+            config_dir_in(&relative).unwrap(),
+            config_dir_in(&env(&[])).unwrap()
+        );
+    }
+
+    #[test]
+    fn the_tasks_file_is_resolved_like_the_config_dir() {
+        let overridden = env(&[
+            ("TODO_CONFIG_DIR", "/srv/todo"),
+            ("XDG_CONFIG_HOME", "/home/me/.xdg"),
+        ]);
+        assert_eq!(
+            default_tasks_path_in(&overridden, false),
+            PathBuf::from("/srv/todo/tasks.json")
+        );
+        let xdg = env(&[("XDG_CONFIG_HOME", "/home/me/.xdg")]);
+        assert_eq!(
+            default_tasks_path_in(&xdg, false),
+            PathBuf::from("/home/me/.xdg/todo/tasks.json")
+        );
+        assert_eq!(
+            default_tasks_path_in(&env(&[]), false),
+            config_dir_in(&env(&[])).unwrap().join("tasks.json")
+        );
+    }
+
+    #[test]
+    fn a_tasks_file_in_the_current_directory_is_kept() {
+        let xdg = env(&[("XDG_CONFIG_HOME", "/home/me/.xdg")]);
+        assert_eq!(
+            default_tasks_path_in(&xdg, true),
+            PathBuf::from("tasks.json")
+        );
+        // The explicit override still wins
+        let overridden = env(&[("TODO_CONFIG_DIR", "/srv/todo")]);
+        assert_eq!(
+            default_tasks_path_in(&overridden, true),
+            PathBuf::from("/srv/todo/tasks.json")
+        );
+    }
+
+    #[test]
+    fn week_boundaries_follow_the_week_start() {
+        let date = |day| NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+        // Sunday the 18th ends a Monday week and starts a Sunday one
+        assert_eq!(WeekStart::Monday.start_of_week(date(18)), date(12));
+        assert_eq!(WeekStart::Monday.end_of_week(date(18)), date(18));
+        assert_eq!(WeekStart::Sunday.start_of_week(date(18)), date(18));
+        assert_eq!(WeekStart::Sunday.end_of_week(date(18)), date(24));
+        // Wednesday the 14th is inside both
+        assert_eq!(WeekStart::Monday.start_of_week(date(14)), date(12));
+        assert_eq!(WeekStart::Sunday.end_of_week(date(14)), date(17));
+    }
+}
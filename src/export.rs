@@ -0,0 +1,278 @@
+use crate::config::WeekStart;
+use crate::task::{due_deadline, Priority, Status, Task};
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// Text formats the task list can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+pub fn export_tasks(tasks: &[Task], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => to_markdown(tasks),
+        ExportFormat::Csv => to_csv(tasks),
+    }
+}
+
+/// A GitHub-style checklist with subtasks nested one level down.
+fn to_markdown(tasks: &[Task]) -> String {
+    let mut out = String::new();
+    for task in tasks {
+        push_markdown_line(&mut out, task, "");
+        for sub_task in &task.sub_tasks {
+            push_markdown_line(&mut out, sub_task, "  ");
+        }
+    }
+    out
+}
+
+fn push_markdown_line(out: &mut String, task: &Task, indent: &str) {
+    let check = match task.status {
+        Status::Done => "x",
+        Status::InProgress => "-",
+        Status::Todo => " ",
+    };
+    out.push_str(&format!("{}- [{}] {}", indent, check, task.description));
+    if let Some(due_date) = &task.due_date {
+        out.push_str(&format!(" (due {})", due_date));
+    }
+    for tag in &task.tags {
+        out.push_str(&format!(" {}", tag));
+    }
+    out.push('\n');
+}
+
+/// One row per task; subtasks name their parent's ID in `parent_id`.
+fn to_csv(tasks: &[Task]) -> String {
+    let mut out = String::from("id,parent_id,description,status,priority,due_date,tags,notes\n");
+    for task in tasks {
+        push_csv_row(&mut out, task, None);
+        for sub_task in &task.sub_tasks {
+            push_csv_row(&mut out, sub_task, Some(task.id));
+        }
+    }
+    out
+}
+
+fn push_csv_row(out: &mut String, task: &Task, parent_id: Option<usize>) {
+    let priority = match task.priority {
+        Priority::High => "high",
+        Priority::Medium => "medium",
+        Priority::Low => "low",
+    };
+    let fields = [
+        task.id.to_string(),
+        parent_id.map(|id| id.to_string()).unwrap_or_default(),
+        task.description.clone(),
+        task.status.label().to_string(),
+        priority.to_string(),
+        task.due_date.clone().unwrap_or_default(),
+        task.tags.join(" "),
+        task.notes.clone().unwrap_or_default(),
+    ];
+    let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    out.push_str(&row.join(","));
+    out.push('\n');
+}
+
+/// Quotes a field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The tasks as a JSON array for other tools. Each task carries the fields
+/// of the tasks file plus a computed `overdue` flag (open and past its
+/// deadline), and so does each of its `sub_tasks`.
+pub fn to_json(tasks: &[Task], now: NaiveDateTime) -> serde_json::Result<String> {
+    let values = tasks
+        .iter()
+        .map(|task| json_task(task, now))
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    serde_json::to_string_pretty(&values)
+}
+
+fn json_task(task: &Task, now: NaiveDateTime) -> serde_json::Result<Value> {
+    let mut value = serde_json::to_value(task)?;
+    if let Value::Object(fields) = &mut value {
+        let overdue = !task.is_done()
+            && task
+                .due_date
+                .as_deref()
+                .and_then(due_deadline)
+                .is_some_and(|deadline| deadline < now);
+        fields.insert("overdue".to_string(), Value::Bool(overdue));
+        let sub_tasks = task
+            .sub_tasks
+            .iter()
+            .map(|sub_task| json_task(sub_task, now))
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        fields.insert("sub_tasks".to_string(), Value::Array(sub_tasks));
+    }
+    Ok(value)
+}
+
+/// Which tasks `count` counts; every condition set must hold.
+#[derive(Debug, Default)]
+pub struct CountQuery {
+    /// Not done
+    pub pending: bool,
+    /// Open and past the deadline
+    pub overdue: bool,
+    /// Open and due today
+    pub today: bool,
+    /// Has every one of these tags (case-insensitive, '#' optional)
+    pub tags: Vec<String>,
+    /// Count subtasks as well as top-level tasks
+    pub subtasks: bool,
+}
+
+/// Counts the tasks matching `query`, for status bars and prompts.
+pub fn count(tasks: &[Task], now: NaiveDateTime, query: &CountQuery) -> usize {
+    let tags: Vec<String> = query
+        .tags
+        .iter()
+        .map(|tag| format!("#{}", tag.trim_start_matches('#')))
+        .collect();
+    let matches = |task: &Task| {
+        let deadline = task.due_date.as_deref().and_then(due_deadline);
+        (!query.pending || !task.is_done())
+            && (!query.overdue || (!task.is_done() && deadline.is_some_and(|d| d < now)))
+            && (!query.today
+                || (!task.is_done()
+                    && deadline.is_some_and(|d| d >= now && d.date() == now.date())))
+            && tags
+                .iter()
+                .all(|tag| task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    };
+    tasks
+        .iter()
+        .map(|task| {
+            let sub_tasks = if query.subtasks {
+                task.sub_tasks
+                    .iter()
+                    .filter(|sub_task| matches(sub_task))
+                    .count()
+            } else {
+                0
+            };
+            usize::from(matches(task)) + sub_tasks
+        })
+        .sum()
+}
+
+/// A plain-text agenda of the open tasks (and subtasks), grouped by when they
+/// are due relative to `now`: overdue first, then today, tomorrow, the rest of
+/// this week (which begins on `week_start`), later, someday (more than
+/// `someday_days` out) and finally the tasks with no due date. Completed tasks
+/// are left out unless `include_completed` is set.
+pub fn agenda(
+    tasks: &[Task],
+    now: NaiveDateTime,
+    week_start: WeekStart,
+    someday_days: Option<u32>,
+    include_completed: bool,
+) -> String {
+    const GROUPS: [&str; 7] = [
+        "Overdue",
+        "Today",
+        "Tomorrow",
+        "This week",
+        "Later",
+        "Someday",
+        "No date",
+    ];
+    let today = now.date();
+    let end_of_week = week_start.end_of_week(today);
+    let someday = someday_days.map(|days| now + chrono::Duration::days(days as i64));
+
+    let mut groups: Vec<Vec<(Option<NaiveDateTime>, String)>> = vec![Vec::new(); GROUPS.len()];
+    let entries = tasks.iter().flat_map(|task| {
+        std::iter::once((task, None)).chain(
+            task.sub_tasks
+                .iter()
+                .map(move |sub_task| (sub_task, Some(task.description.as_str()))),
+        )
+    });
+    for (task, parent) in entries {
+        if task.is_done() && !include_completed {
+            continue;
+        }
+        let deadline = task.due_date.as_deref().and_then(due_deadline);
+        // Undated subtasks are covered by their parent's entry
+        if deadline.is_none() && parent.is_some() {
+            continue;
+        }
+        let group = match deadline {
+            None => 6,
+            Some(deadline) if deadline < now => 0,
+            Some(deadline) if deadline.date() == today => 1,
+            Some(deadline) if deadline.date() == today.succ_opt().unwrap_or(today) => 2,
+            Some(deadline) if deadline.date() <= end_of_week => 3,
+            Some(deadline) if someday.is_some_and(|someday| deadline > someday) => 5,
+            Some(_) => 4,
+        };
+        let mut line = match (deadline, task.due_date.as_deref()) {
+            (Some(deadline), Some(due_date)) => {
+                let timed = due_date.contains(' ');
+                match group {
+                    1 | 2 if timed => format!("{}  ", deadline.format("%H:%M")),
+                    1 | 2 => String::new(),
+                    _ if timed => format!("{}  ", deadline.format("%a %b %-d %H:%M")),
+                    _ => format!("{}  ", deadline.format("%a %b %-d")),
+                }
+            }
+            _ => String::new(),
+        };
+        if task.is_done() {
+            line.push_str("[done] ");
+        }
+        line.push_str(&task.description);
+        if let Some(parent) = parent {
+            line.push_str(&format!(" (in {})", parent));
+        }
+        groups[group].push((deadline, line));
+    }
+
+    let mut out = String::new();
+    for (name, mut entries) in GROUPS.iter().zip(groups) {
+        if entries.is_empty() {
+            continue;
+        }
+        entries.sort_by_key(|(deadline, _)| *deadline);
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{} ({})\n", name, entries.len()));
+        for (_, line) in entries {
+            out.push_str(&format!("  - {}\n", line));
+        }
+    }
+    if out.is_empty() {
+        out.push_str("Nothing to do.\n");
+    }
+    out
+}
@@ -0,0 +1,99 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Advisory lock held next to the tasks file (`tasks.json.lock`) so a second
+/// instance doesn't overwrite the first one's saves. The file holds the owner's
+/// PID and is removed when the lock is dropped.
+pub struct LockFile {
+    path: PathBuf,
+}
+
+pub enum LockError {
+    /// Another running instance owns the lock
+    HeldBy(u32),
+    Io(io::Error),
+}
+
+impl LockFile {
+    pub fn lock_path(tasks_path: &Path) -> PathBuf {
+        let mut path = tasks_path.as_os_str().to_owned();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+
+    /// Takes the lock, reclaiming it if the instance that left it is no longer
+    /// running.
+    pub fn acquire(tasks_path: &Path) -> Result<LockFile, LockError> {
+        let path = Self::lock_path(tasks_path);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id()).map_err(LockError::Io)?;
+                    return Ok(LockFile { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let owner = fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|content| content.trim().parse::<u32>().ok());
+                    match owner {
+                        Some(pid) if pid != std::process::id() && process_is_alive(pid) => {
+                            return Err(LockError::HeldBy(pid));
+                        }
+                        // Stale or unreadable: the owner is gone, so take over
+                        _ => fs::remove_file(&path).map_err(LockError::Io)?,
+                    }
+                }
+                Err(e) => return Err(LockError::Io(e)),
+            }
+        }
+        Err(LockError::Io(io::Error::other(format!(
+            "could not take over {}",
+            path.display()
+        ))))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_alive(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_is_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+/// `tasklist` prints a CSV row quoting the PID when the process exists and an
+/// "INFO: No tasks" line otherwise. If it can't be run, assume the owner is
+/// still running.
+#[cfg(windows)]
+fn process_is_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+        .unwrap_or(true)
+}
+
+/// Without a liveness check, assume the owner is still running.
+#[cfg(not(any(unix, windows)))]
+fn process_is_alive(_pid: u32) -> bool {
+    true
+}
@@ -1,11 +1,20 @@
 use crate::{
-    app::{App, AppMode},
-    theme::ThemeManager,
+    action::{normal_mode_action, Action},
+    app::{App, AppMode, Level},
+    config::{default_tasks_path, DateDialect, Settings},
+    export::{agenda, count, export_tasks, to_json, CountQuery, ExportFormat},
+    lock::{LockError, LockFile},
+    task::{load_tasks, FileFormat},
+    theme::{validate_theme_files, ThemeManager},
     ui::ui,
 };
-use clap::Parser;
+use chrono::Local;
+use clap::{Parser, Subcommand};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -13,9 +22,19 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
-use std::{error::Error, io};
+use std::{
+    error::Error,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
+mod action;
 mod app;
+mod config;
+mod export;
+mod lock;
 mod task;
 mod theme;
 mod ui;
@@ -25,18 +44,188 @@ mod ui;
 #[command(about = "A terminal-based todo list with advanced features")]
 #[command(version = "1.0")]
 struct Cli {
-    /// Theme to use (catppuccin-mocha, catppuccin-latte, dracula, gruvbox-dark, nord)
-    #[arg(short, long, default_value = "catppuccin-mocha")]
-    theme: String,
+    /// Theme to use (catppuccin-mocha, catppuccin-latte, dracula, gruvbox-dark, nord,
+    /// monochrome) [default: catppuccin-mocha, or monochrome when NO_COLOR is set]
+    #[arg(short, long)]
+    theme: Option<String>,
 
     /// List available themes
     #[arg(long)]
     list_themes: bool,
+
+    /// Check the custom theme files and report any problems, then exit
+    #[arg(long)]
+    validate_themes: bool,
+
+    /// How to read ambiguous dates like "1/6" (overrides settings.json)
+    #[arg(long, value_enum)]
+    date_format: Option<DateDialect>,
+
+    /// Tasks file to use; the extension selects the format [default:
+    /// tasks.json, in $TODO_CONFIG_DIR when set]
+    #[arg(short, long, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// Start with the filters of a preset saved with F in the app
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Add a task from ~/.config/todo/templates/<NAME>.json on startup
+    #[arg(long, value_name = "NAME")]
+    from_template: Option<String>,
+
+    /// Draw with plain ASCII symbols and borders instead of unicode glyphs
+    /// (the default when the locale isn't UTF-8)
+    #[arg(long)]
+    ascii: bool,
+
+    /// Open without saving any changes (no lock is taken)
+    #[arg(long)]
+    read_only: bool,
+
+    /// Only save when quitting instead of after every change
+    #[arg(long)]
+    no_autosave: bool,
+
+    /// Don't say how many tasks are overdue or due today on startup
+    #[arg(long)]
+    no_summary: bool,
+
+    /// Start with the default view instead of the one saved on the last quit
+    /// (filters, sort, collapsed tasks, zoom), and don't save it this time
+    #[arg(long)]
+    fresh: bool,
+
+    /// Print the tasks in this format and exit instead of opening the app
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    export: Option<ExportFormat>,
+
+    /// Tags to add to every new task, comma-separated (on top of settings.json)
+    #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+    default_tags: Vec<String>,
+
+    /// With --export, only include tasks matching this search query
+    #[arg(long, value_name = "QUERY", requires = "export")]
+    filter: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print open tasks grouped by due day (overdue, today, tomorrow, ...)
+    Agenda {
+        /// Include completed tasks
+        #[arg(long)]
+        all: bool,
+    },
+    /// Print the tasks as a Markdown checklist, or as JSON with --json
+    List {
+        /// Print a JSON array of tasks (the tasks file's fields plus
+        /// `overdue`) for scripts and status bars
+        #[arg(long)]
+        json: bool,
+
+        /// Only include tasks matching this search query
+        #[arg(long, value_name = "QUERY")]
+        filter: Option<String>,
+    },
+    /// Print how many tasks match, for prompts and status bars; the
+    /// conditions combine (all must hold)
+    Count {
+        /// Only tasks that aren't done
+        #[arg(long)]
+        pending: bool,
+
+        /// Only open tasks past their due date
+        #[arg(long)]
+        overdue: bool,
+
+        /// Only open tasks due later today
+        #[arg(long)]
+        today: bool,
+
+        /// Only tasks with this tag (repeat for several)
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Count subtasks too
+        #[arg(long)]
+        subtasks: bool,
+    },
+    /// Add a task without opening the app, parsed like the add popup
+    /// ("buy milk #shopping tomorrow"). With `-`, or no text and piped
+    /// input, each line of stdin becomes a task
+    Add { text: Vec<String> },
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
+    if cli.validate_themes {
+        let files = validate_theme_files()?;
+        if files.is_empty() {
+            println!("No custom theme files found.");
+        }
+        let mut failed = false;
+        for (path, problems) in files {
+            if problems.is_empty() {
+                println!("ok    {}", path.display());
+                continue;
+            }
+            failed = true;
+            println!("error {}", path.display());
+            for problem in problems {
+                println!("      {}", problem);
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let settings = {
+        let mut settings = Settings::load();
+        if let Some(date_format) = cli.date_format {
+            settings.date_dialect = date_format;
+        }
+        settings.resolve_ascii(cli.ascii);
+        settings
+            .default_tags
+            .extend(cli.default_tags.iter().cloned());
+        settings
+    };
+
+    // Counting is polled by status bars, so it skips everything but the
+    // settings, which decide how stored due dates are read and moved on
+    if let Some(Command::Count {
+        pending,
+        overdue,
+        today,
+        tag,
+        subtasks,
+    }) = &cli.command
+    {
+        let file = cli.file.clone().unwrap_or_else(default_tasks_path);
+        let query = CountQuery {
+            pending: *pending,
+            overdue: *overdue,
+            today: *today,
+            tags: tag.clone(),
+            subtasks: *subtasks,
+        };
+        match load_tasks(&file, &settings) {
+            Ok(list) => println!("{}", count(&list.tasks, Local::now().naive_local(), &query)),
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Initialize theme manager
     let mut theme_manager = ThemeManager::new();
 
@@ -50,20 +239,232 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Set the requested theme
-    if let Err(e) = theme_manager.set_theme(&cli.theme) {
+    // NO_COLOR (when set and not empty) only changes the default theme, so
+    // an explicit --theme still wins
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let theme = cli.theme.as_deref().unwrap_or(if no_color {
+        "monochrome"
+    } else {
+        "catppuccin-mocha"
+    });
+    if let Err(e) = theme_manager.set_theme(theme) {
         eprintln!("Warning: {}", e);
         eprintln!("Using default theme instead.");
     }
 
+    let file = cli.file.clone().unwrap_or_else(default_tasks_path);
+    // The default file lives in the config directory, which may not exist yet
+    if let (None, Some(dir)) = (&cli.file, file.parent()) {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Error: could not create {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = FileFormat::from_path(&file).check_supported() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    if let Some(Command::Agenda { all }) = cli.command {
+        match load_tasks(&file, &settings) {
+            Ok(list) => print!(
+                "{}",
+                agenda(
+                    &list.tasks,
+                    Local::now().naive_local(),
+                    settings.week_start,
+                    settings.someday_days,
+                    all
+                )
+            ),
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Add { text }) = &cli.command {
+        let lines: Vec<String> = match text.as_slice() {
+            [dash] if dash == "-" => io::stdin().lines().collect::<Result<_, _>>()?,
+            [] if !io::stdin().is_terminal() => io::stdin().lines().collect::<Result<_, _>>()?,
+            [] => {
+                eprintln!("Error: give the task to add, or pipe tasks in one per line");
+                std::process::exit(1);
+            }
+            words => vec![words.join(" ")],
+        };
+        // The running app would overwrite tasks added behind its back
+        let lock = match LockFile::acquire(&file) {
+            Ok(lock) => Some(lock),
+            Err(LockError::HeldBy(pid)) => {
+                eprintln!(
+                    "Error: {} is open in another instance (PID {})",
+                    file.display(),
+                    pid
+                );
+                std::process::exit(1);
+            }
+            Err(LockError::Io(e)) => {
+                eprintln!("Warning: could not create a lock file: {}", e);
+                None
+            }
+        };
+        let mut app = App::new_with_theme(theme_manager, settings, file.clone());
+        if let Some((message, ..)) = app
+            .messages
+            .iter()
+            .find(|(.., level)| *level == Level::Error)
+        {
+            eprintln!("Error: {}", message);
+            drop(lock);
+            std::process::exit(1);
+        }
+        let added = lines
+            .iter()
+            .filter(|line| app.add_task_from_text(line))
+            .count();
+        if !app.save() {
+            eprintln!("Error: {}", app.save_error.unwrap_or_default());
+            drop(lock);
+            std::process::exit(1);
+        }
+        println!("Added {} task(s) to {}", added, file.display());
+        return Ok(());
+    }
+
+    if let Some(Command::List { json, filter }) = &cli.command {
+        let mut app = App::new_with_theme(theme_manager, settings, file.clone());
+        // List what was asked for, not whatever the last session filtered
+        app.reset_view();
+        if let Some((message, ..)) = app
+            .messages
+            .iter()
+            .find(|(.., level)| *level == Level::Error)
+        {
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        if let Some(query) = filter {
+            app.add_search_filters(query);
+        }
+        let tasks = app.displayed_task_tree();
+        if *json {
+            println!("{}", to_json(&tasks, Local::now().naive_local())?);
+        } else {
+            print!("{}", export_tasks(&tasks, ExportFormat::Markdown));
+        }
+        return Ok(());
+    }
+
+    if let Some(format) = cli.export {
+        let mut app = App::new_with_theme(theme_manager, settings, file.clone());
+        // Export what was asked for, not whatever the last session filtered
+        app.reset_view();
+        if let Some((message, ..)) = app
+            .messages
+            .iter()
+            .find(|(.., level)| *level == Level::Error)
+        {
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        if let Some(query) = &cli.filter {
+            app.add_search_filters(query);
+        }
+        print!("{}", export_tasks(&app.displayed_task_tree(), format));
+        return Ok(());
+    }
+
+    let mut read_only = cli.read_only;
+    let lock = if read_only {
+        None
+    } else {
+        match LockFile::acquire(&file) {
+            Ok(lock) => Some(lock),
+            Err(LockError::HeldBy(pid)) => {
+                eprint!(
+                    "{} is open in another instance (PID {}). Open read-only? [y/N] ",
+                    file.display(),
+                    pid
+                );
+                io::stderr().flush()?;
+                let mut answer = String::new();
+                io::stdin().read_line(&mut answer)?;
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    return Ok(());
+                }
+                read_only = true;
+                None
+            }
+            Err(LockError::Io(e)) => {
+                eprintln!("Warning: could not create a lock file: {}", e);
+                None
+            }
+        }
+    };
+
+    let mut app = App::new_with_theme(theme_manager, settings, file.clone());
+    app.autosave = !cli.no_autosave;
+    if cli.fresh {
+        app.reset_view();
+    }
+    app.read_only = read_only;
+    if app.settings.startup_summary && !cli.no_summary {
+        app.due_summary = app.due_summary_text();
+    }
+
+    if let Some(preset) = &cli.preset {
+        if let Err(e) = app.apply_filter_preset_named(preset) {
+            eprintln!("Error: {}", e);
+            drop(lock);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(template) = &cli.from_template {
+        if let Err(e) = app.add_from_template(template) {
+            eprintln!("Error: {}", e);
+            drop(lock);
+            std::process::exit(1);
+        }
+        app.select_first();
+    }
+
+    // Put the terminal back and release the lock even if we panic, since
+    // raw mode would otherwise leave the shell unusable
+    let lock_path = lock.as_ref().map(|lock| lock.path().to_path_buf());
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
+        if let Some(path) = &lock_path {
+            let _ = std::fs::remove_file(path);
+        }
+        default_hook(info);
+    }));
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Bracketed paste delivers a paste as one event, so its newlines don't
+    // arrive as Enter presses that submit the input halfway through
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let app = App::new_with_theme(theme_manager);
+    // run the app
     let res = run_app(&mut terminal, app);
 
     // restore terminal
@@ -71,9 +472,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
+    drop(lock);
 
     if let Err(err) = res {
         println!("{:?}", err)
@@ -82,85 +485,133 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+const TICK_RATE: Duration = Duration::from_secs(1);
+
+/// The modifiers held with a key, as far as bindings care. Shift is already
+/// part of a typed character ('A', '*'), so it only counts on other keys,
+/// like Shift+Up. Ctrl+Alt on a character is how some terminals report
+/// AltGr, so that types the character as usual.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Chord {
+    Plain,
+    Shift,
+    Ctrl,
+    Alt,
+}
+
+impl Chord {
+    fn of(key: &KeyEvent) -> Self {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let character = matches!(key.code, KeyCode::Char(_));
+        if ctrl && alt && character {
+            Chord::Plain
+        } else if ctrl {
+            Chord::Ctrl
+        } else if alt {
+            Chord::Alt
+        } else if key.modifiers.contains(KeyModifiers::SHIFT) && !character {
+            Chord::Shift
+        } else {
+            Chord::Plain
+        }
+    }
+}
+
+/// Translates a key press into an action for the current mode. Normal mode
+/// goes through the binding table in action.rs; outside it (or mid leader
+/// sequence) keys map to generic input actions.
+///
+/// Keys held with a modifier are looked up in `chord_action` first. A Shift
+/// chord with no binding of its own acts as the bare key, but Ctrl and Alt
+/// chords never do, so binding one can't collide with the plain key: an
+/// unbound Ctrl+D is ignored rather than deleting like d.
+fn key_action(app: &App, key: KeyEvent) -> Option<Action> {
+    let chord = Chord::of(&key);
+    if chord != Chord::Plain {
+        if let Some(action) = chord_action(app, key.code, chord) {
+            return Some(action);
+        }
+        if chord != Chord::Shift {
+            return None;
+        }
+    }
+    if matches!(app.mode, AppMode::Normal) && app.leader_keys.is_none() {
+        return normal_mode_action(app, key.code);
+    }
+    Some(match key.code {
+        KeyCode::Enter => Action::Submit,
+        KeyCode::Esc => Action::Cancel,
+        KeyCode::Up => Action::Previous,
+        KeyCode::Down => Action::Next,
+        KeyCode::Tab => Action::Tab,
+        KeyCode::Backspace => Action::Backspace,
+        KeyCode::PageUp => Action::PageUp,
+        KeyCode::PageDown => Action::PageDown,
+        KeyCode::F(1) => Action::ShowHelp,
+        KeyCode::Char(c) => Action::Input(c),
+        _ => return None,
+    })
+}
+
+/// Bindings for keys held with a modifier, in any mode. Only terminals that
+/// report modifiers on keys like Enter can tell those chords apart.
+fn chord_action(app: &App, code: KeyCode, chord: Chord) -> Option<Action> {
+    Some(match (chord, code) {
+        // Raw mode turns Ctrl+C into a key press instead of a signal, so it
+        // is bound here and leaves (saving) the same way as q
+        (Chord::Ctrl, KeyCode::Char('c' | 'C')) => Action::Quit,
+        (Chord::Ctrl, KeyCode::Enter) if matches!(app.mode, AppMode::Insert) => {
+            Action::SubmitAndContinue
+        }
+        _ => return None,
+    })
+}
+
+/// Translates a mouse event over the task list into a drag action. Only the
+/// list in Normal mode reacts to the mouse.
+fn mouse_action(app: &App, mouse: MouseEvent) -> Option<Action> {
+    if !matches!(app.mode, AppMode::Normal) {
+        return None;
+    }
+    let row = app.row_at(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => row.map(Action::DragStart),
+        MouseEventKind::Drag(MouseButton::Left) if app.drag.is_some() => row.map(Action::DragTo),
+        MouseEventKind::Up(MouseButton::Left) if app.drag.is_some() => Some(Action::DragEnd),
+        _ => None,
+    }
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    let mut last_tick = Instant::now();
     loop {
         terminal.draw(|f| ui(f, &mut app))?;
 
-        if let Event::Key(key) = event::read()? {
-            match app.mode {
-                AppMode::Normal => match key.code {
-                    KeyCode::Char('q') => {
-                        app.save();
-                        return Ok(());
-                    }
-                    KeyCode::Down => app.next(),
-                    KeyCode::Up => app.previous(),
-                    KeyCode::Enter => app.toggle_completed(),
-                    KeyCode::Char('a') => {
-                        app.adding_subtask = false;
-                        app.mode = AppMode::Insert;
-                    }
-                    KeyCode::Char('d') => app.delete_task(),
-                    KeyCode::Char('p') => app.cycle_priority(),
-                    KeyCode::Char('D') => app.mode = AppMode::DateInput,
-                    KeyCode::Char('s') => {
-                        app.adding_subtask = true;
-                        app.mode = AppMode::Insert;
-                    }
-                    KeyCode::Char('/') => app.mode = AppMode::Search,
-                    KeyCode::Char('f') => app.toggle_focus_mode(),
-                    KeyCode::Char('C') => app.delete_all_completed(),
-                    KeyCode::Char('h') | KeyCode::F(1) => app.show_help(),
-                    KeyCode::Char('t') => app.cycle_theme(),
-                    KeyCode::Char('+') => app.zoom_in(),
-                    KeyCode::Char('-') => app.zoom_out(),
-                    _ => {}
-                },
-                AppMode::Insert => match key.code {
-                    KeyCode::Enter => app.add_task(),
-                    KeyCode::Char(c) => app.input.push(c),
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Esc => {
-                        app.adding_subtask = false;
-                        app.mode = AppMode::Normal;
+        let timeout = TICK_RATE.saturating_sub(last_tick.elapsed());
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) => {
+                    if let Some(action) = key_action(&app, key) {
+                        app.handle_action(action);
                     }
-                    _ => {}
-                },
-                AppMode::DateInput => match key.code {
-                    KeyCode::Enter => app.set_due_date(),
-                    KeyCode::Char(c) => app.date_input.push(c),
-                    KeyCode::Backspace => {
-                        app.date_input.pop();
+                }
+                Event::Mouse(mouse) => {
+                    if let Some(action) = mouse_action(&app, mouse) {
+                        app.handle_action(action);
                     }
-                    KeyCode::Esc => app.mode = AppMode::Normal,
-                    _ => {}
-                },
-                AppMode::Search => match key.code {
-                    KeyCode::Enter | KeyCode::Esc => app.mode = AppMode::Normal,
-                    KeyCode::Char(c) => app.search_input.push(c),
-                    KeyCode::Backspace => {
-                        app.search_input.pop();
-                    }
-                    _ => {}
-                },
-                AppMode::Confirm => match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        app.execute_confirm_action();
-                    }
-                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                        app.hide_confirm_dialog();
-                    }
-                    _ => {}
-                },
-                AppMode::Help => match key.code {
-                    KeyCode::Esc | KeyCode::Char('h') | KeyCode::F(1) | KeyCode::Char('q') => {
-                        app.hide_help();
-                    }
-                    _ => {}
-                },
+                }
+                Event::Paste(text) => app.handle_action(Action::Paste(text)),
+                _ => {}
             }
         }
+        if app.should_quit {
+            return Ok(());
+        }
+
+        if last_tick.elapsed() >= TICK_RATE {
+            app.on_tick();
+            last_tick = Instant::now();
+        }
     }
 }
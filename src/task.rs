@@ -1,42 +1,1022 @@
+use crate::config::{DateDialect, OverdueRecurrence, Settings};
+use crate::theme::SerializableColor;
+use chrono::{
+    DateTime, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+};
 use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufReader, BufWriter};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
 use std::path::Path;
+use std::time::Duration;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// On-disk format of a due date that includes a time.
+pub const DUE_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// How urgent a due date is relative to now.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DueState {
+    Overdue,
+    /// Due today or within the configured "soon" window
+    Soon,
+    Upcoming,
+    /// Due further out than the configured "someday" horizon
+    Someday,
+}
+
+/// Parses a stored due date into its deadline. Date-only values are due at
+/// the end of that day.
+pub fn due_deadline(due_date: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(due_date, DUE_DATETIME_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(due_date, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+        })
+}
+
+/// When a stored due date falls, for sorting: timed values at their time and
+/// date-only values at `date_only_time` on that day.
+pub fn due_sort_time(due_date: &str, date_only_time: NaiveTime) -> Option<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(due_date, DUE_DATETIME_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(due_date, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(date_only_time))
+        })?;
+    Some(local_datetime(naive))
+}
+
+/// The moment a local wall-clock time names. A time a DST change repeats is
+/// the earlier of the two; one it skips is read as UTC, so it still lands
+/// within a few hours of what was meant instead of failing.
+pub fn local_datetime(naive: NaiveDateTime) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+/// Reads a due date written in any of the shapes found in older or
+/// hand-edited files (ISO with a `T`, slashes, seconds, month names, or
+/// natural language like "next friday", read relative to `now`) and returns
+/// it in the stored form: `YYYY-MM-DD HH:MM`, or `YYYY-MM-DD` when no time
+/// of day was given. Numeric dates like "1/6/2026" follow `dialect`.
+pub fn normalize_due_date(raw: &str, now: DateTime<Local>, dialect: DateDialect) -> Option<String> {
+    const DATETIME_FORMATS: [&str; 5] = [
+        DUE_DATETIME_FORMAT,
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y/%m/%d %H:%M",
+    ];
+    const DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%d %b %Y", "%b %d %Y", "%B %d, %Y"];
+    let raw = raw.trim();
+    if let Some(datetime) = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+    {
+        return Some(datetime.format(DUE_DATETIME_FORMAT).to_string());
+    }
+    if let Some(date) = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+    {
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+    // As when typing a task, a parsed midnight means no time was given
+    let parsed = chrono_english::parse_date_string(raw, now, dialect.to_chrono()).ok()?;
+    Some(if parsed.hour() == 0 && parsed.minute() == 0 {
+        parsed.format("%Y-%m-%d").to_string()
+    } else {
+        parsed.format(DUE_DATETIME_FORMAT).to_string()
+    })
+}
+
+pub fn due_state(
+    due_date: &str,
+    now: NaiveDateTime,
+    soon: chrono::Duration,
+    someday: Option<chrono::Duration>,
+) -> Option<DueState> {
+    let deadline = due_deadline(due_date)?;
+    Some(if deadline < now {
+        DueState::Overdue
+    } else if deadline.date() == now.date() || deadline - now <= soon {
+        DueState::Soon
+    } else if someday.is_some_and(|someday| deadline - now > someday) {
+        DueState::Someday
+    } else {
+        DueState::Upcoming
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub enum Priority {
     Low,
+    #[default]
     Medium,
     High,
 }
 
+/// A rough size for planning: a t-shirt size or a number of story points.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Effort {
+    Small,
+    Medium,
+    Large,
+    Points(u32),
+}
+
+impl Effort {
+    /// Reads a size ("s", "m", "l" or the full words) or a number of points,
+    /// optionally followed by "pt" or "pts".
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        match input.as_str() {
+            "s" | "small" => Some(Effort::Small),
+            "m" | "med" | "medium" => Some(Effort::Medium),
+            "l" | "large" => Some(Effort::Large),
+            points => points
+                .trim_end_matches("pts")
+                .trim_end_matches("pt")
+                .trim()
+                .parse()
+                .ok()
+                .map(Effort::Points),
+        }
+    }
+
+    /// Short form shown on the task's badge, like "M" or "5pt".
+    pub fn label(self) -> String {
+        match self {
+            Effort::Small => "S".to_string(),
+            Effort::Medium => "M".to_string(),
+            Effort::Large => "L".to_string(),
+            Effort::Points(points) => format!("{}pt", points),
+        }
+    }
+}
+
+/// How often a task comes back: completing it adds the next occurrence.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    /// Every this many days
+    Days(u32),
+}
+
+impl Recurrence {
+    /// Reads "daily", "weekly", "monthly" or "yearly" (or "d", "w", "m",
+    /// "y"), "every day" or "every week" style units, or an interval in days
+    /// like "3d" or "every 3 days". One day is the same as daily.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        let input = input.strip_prefix("every").unwrap_or(&input).trim();
+        match input {
+            "d" | "day" | "daily" => Some(Recurrence::Daily),
+            "w" | "week" | "weekly" => Some(Recurrence::Weekly),
+            "m" | "month" | "monthly" => Some(Recurrence::Monthly),
+            "y" | "year" | "yearly" => Some(Recurrence::Yearly),
+            interval => {
+                let days = interval
+                    .strip_suffix("days")
+                    .or_else(|| interval.strip_suffix("day"))
+                    .or_else(|| interval.strip_suffix('d'))?
+                    .trim()
+                    .parse()
+                    .ok()?;
+                match days {
+                    0 => None,
+                    1 => Some(Recurrence::Daily),
+                    days => Some(Recurrence::Days(days)),
+                }
+            }
+        }
+    }
+
+    /// Shown on the task's badge, like "weekly" or "every 3 days".
+    pub fn label(self) -> String {
+        match self {
+            Recurrence::Daily => "daily".to_string(),
+            Recurrence::Weekly => "weekly".to_string(),
+            Recurrence::Monthly => "monthly".to_string(),
+            Recurrence::Yearly => "yearly".to_string(),
+            Recurrence::Days(days) => format!("every {} days", days),
+        }
+    }
+
+    /// The occurrence after `date`. Months and years keep the day of the
+    /// month, or use the last day of a shorter month.
+    pub fn next_after(self, date: NaiveDate) -> NaiveDate {
+        let next = match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => date.checked_add_days(Days::new(7)),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+            Recurrence::Yearly => date.checked_add_months(Months::new(12)),
+            Recurrence::Days(days) => date.checked_add_days(Days::new(days as u64)),
+        };
+        next.unwrap_or(date)
+    }
+}
+
+/// A stored due date moved one `recurrence` step on, keeping its time of day
+/// if it has one. `None` for a due date that isn't in the stored form.
+pub fn advance_due_date(due_date: &str, recurrence: Recurrence) -> Option<String> {
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(due_date, DUE_DATETIME_FORMAT) {
+        let next = recurrence
+            .next_after(datetime.date())
+            .and_time(datetime.time());
+        return Some(next.format(DUE_DATETIME_FORMAT).to_string());
+    }
+    let date = NaiveDate::parse_from_str(due_date, "%Y-%m-%d").ok()?;
+    Some(recurrence.next_after(date).format("%Y-%m-%d").to_string())
+}
+
+/// Where a task is in its lifecycle. Only `Done` counts as completed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    /// The state Enter moves a task to: todo, then in progress, then done,
+    /// then back to todo.
+    pub fn next(self) -> Self {
+        match self {
+            Status::Todo => Status::InProgress,
+            Status::InProgress => Status::Done,
+            Status::Done => Status::Todo,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in progress",
+            Status::Done => "done",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
+    /// Optional in templates, which get fresh IDs when instantiated
+    #[serde(default)]
     pub id: usize,
     pub description: String,
-    pub completed: bool,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
     pub priority: Priority,
+    #[serde(default)]
     pub due_date: Option<String>,
+    #[serde(default)]
     pub sub_tasks: Vec<Task>,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Files written before this field existed get the time they were first
+    /// loaded, since the real creation time is unknown.
+    #[serde(default = "Local::now")]
+    pub created_at: DateTime<Local>,
+    /// Set when the task is completed and cleared when it is reopened
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+    /// Overrides the description color regardless of priority
+    #[serde(default)]
+    pub color: Option<SerializableColor>,
+    /// Pinned tasks are always listed first, whatever the sort mode
+    #[serde(default)]
+    pub pinned: bool,
+    /// IDs of top-level tasks that must be finished before this one
+    #[serde(default)]
+    pub blocked_by: Vec<usize>,
+    /// How long the task is expected to take
+    #[serde(default)]
+    pub estimate: Option<Duration>,
+    /// Planning size, independent of the time estimate
+    #[serde(default)]
+    pub effort: Option<Effort>,
+    /// Completing a recurring task adds its next occurrence, which takes the
+    /// recurrence over
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Time accumulated by the task timer
+    #[serde(default)]
+    pub time_spent: Duration,
+    /// Subtasks are hidden in the list while set
+    #[serde(default)]
+    pub collapsed: bool,
 }
 
-pub fn load_tasks<P: AsRef<Path>>(path: P) -> io::Result<Vec<Task>> {
-    File::open(path)
-        .map(BufReader::new)
-        .and_then(|reader| {
-            serde_json::from_reader(reader).map_err(io::Error::other)
-        })
-        .or_else(|_| Ok(Vec::new()))
+impl Task {
+    pub fn is_done(&self) -> bool {
+        self.status == Status::Done
+    }
+}
+
+/// Parses a duration typed as hours and/or minutes, e.g. "1h30m", "2h",
+/// "45m" or a bare "45" (minutes).
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim().to_lowercase().replace(' ', "");
+    if let Ok(minutes) = input.parse::<u64>() {
+        return Some(Duration::from_secs(minutes * 60));
+    }
+    let re = regex::Regex::new(r"^(?:(\d+)h)?(?:(\d+)m(?:in)?)?$").ok()?;
+    let caps = re.captures(&input)?;
+    if caps.get(1).is_none() && caps.get(2).is_none() {
+        return None;
+    }
+    let part = |i: usize| {
+        caps.get(i)
+            .and_then(|m| m.as_str().parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    Some(Duration::from_secs(part(1) * 3600 + part(2) * 60))
+}
+
+/// Formats a duration to the minute, like "1h 05m" or "25m".
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// On-disk encoding of the tasks file, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+    Ron,
+}
+
+impl FileFormat {
+    /// Infers the format from the extension; anything unrecognized is JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref()
+        {
+            Some("toml") => FileFormat::Toml,
+            Some("ron") => FileFormat::Ron,
+            _ => FileFormat::Json,
+        }
+    }
+
+    /// TOML and RON are recognized but not built in yet: their serde
+    /// backends aren't among this crate's dependencies.
+    pub fn check_supported(self) -> io::Result<()> {
+        match self {
+            FileFormat::Json => Ok(()),
+            FileFormat::Toml | FileFormat::Ron => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{:?} task files are not supported yet; use a .json file",
+                    self
+                ),
+            )),
+        }
+    }
+}
+
+/// Version written by `save_tasks`. Version 1 files are a bare array of
+/// tasks; from version 2 on the tasks are wrapped in a `TasksFile`, version 3
+/// adds the `next_id` counter, version 4 replaces the `completed` flag with
+/// a `status` and version 5 adds `recurrence`. Version 5 reads like version
+/// 4, but older builds refuse it instead of dropping the recurrences.
+pub const TASKS_FILE_VERSION: u32 = 5;
+
+/// Saved with borrowed tasks, loaded into owned ones.
+#[derive(Serialize, Deserialize)]
+struct TasksFile<T> {
+    version: u32,
+    /// Next unused top-level ID; IDs are never reused, even after deletes
+    #[serde(default)]
+    next_id: usize,
+    tasks: T,
+}
+
+/// The loaded tasks and the next top-level ID to hand out.
+pub struct TaskList {
+    pub tasks: Vec<Task>,
+    pub next_id: usize,
+    /// What `validate_and_repair` fixed while loading, one sentence each
+    pub repairs: Vec<String>,
+}
+
+/// Loads tasks from any known file version, migrating older shapes. A
+/// missing file is an empty list; an unreadable one is an error, so callers
+/// never mistake it for having no tasks. `settings` decide how due dates in
+/// other shapes are read.
+pub fn load_tasks<P: AsRef<Path>>(path: P, settings: &Settings) -> io::Result<TaskList> {
+    let path = path.as_ref();
+    FileFormat::from_path(path).check_supported()?;
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(TaskList {
+                tasks: Vec::new(),
+                next_id: 1,
+                repairs: Vec::new(),
+            })
+        }
+        Err(e) => return Err(e),
+    };
+    let value: serde_json::Value =
+        serde_json::from_reader(BufReader::new(file)).map_err(io::Error::other)?;
+    let mut list = migrate_tasks(value).map_err(io::Error::other)?;
+    validate_and_repair(&mut list, settings);
+    Ok(list)
 }
 
-pub fn save_tasks<P: AsRef<Path>>(path: P, tasks: &[Task]) -> io::Result<()> {
+fn migrate_tasks(mut value: serde_json::Value) -> Result<TaskList, String> {
+    let version = match &value {
+        serde_json::Value::Array(_) => 1,
+        serde_json::Value::Object(fields) => fields
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .ok_or("missing \"version\" field")?
+            as u32,
+        _ => return Err("expected a list of tasks".to_string()),
+    };
+    if version < 4 {
+        let tasks = if version == 1 {
+            Some(&mut value)
+        } else {
+            value.get_mut("tasks")
+        };
+        if let Some(serde_json::Value::Array(tasks)) = tasks {
+            tasks.iter_mut().for_each(migrate_completed_flag);
+        }
+    }
+    match version {
+        1 => serde_json::from_value(value)
+            .map(|tasks| TaskList {
+                tasks,
+                next_id: 0,
+                repairs: Vec::new(),
+            })
+            .map_err(|e| e.to_string()),
+        2..=TASKS_FILE_VERSION => serde_json::from_value::<TasksFile<Vec<Task>>>(value)
+            .map(|file| TaskList {
+                tasks: file.tasks,
+                next_id: file.next_id,
+                repairs: Vec::new(),
+            })
+            .map_err(|e| e.to_string()),
+        version => Err(format!(
+            "file version {} is newer than this app supports ({})",
+            version, TASKS_FILE_VERSION
+        )),
+    }
+}
+
+/// Turns the pre-version-4 `completed` flag of a task and its subtasks into a
+/// `status`: `true` becomes `Done` and `false` becomes `Todo`.
+fn migrate_completed_flag(task: &mut serde_json::Value) {
+    let Some(fields) = task.as_object_mut() else {
+        return;
+    };
+    if let Some(completed) = fields.remove("completed") {
+        let status = if completed.as_bool() == Some(true) {
+            "Done"
+        } else {
+            "Todo"
+        };
+        fields.entry("status").or_insert(status.into());
+    }
+    if let Some(serde_json::Value::Array(sub_tasks)) = fields.get_mut("sub_tasks") {
+        sub_tasks.iter_mut().for_each(migrate_completed_flag);
+    }
+}
+
+/// Fixes what hand-edited or merged files can get wrong, recording each fix
+/// in `list.repairs`: repeated IDs, `blocked_by` entries naming tasks that
+/// don't exist (or the task itself), and dependency cycles, which would
+/// leave every task in them blocked forever. Due dates are rewritten in the
+/// standard form, reading numeric ones in the configured date dialect.
+pub fn validate_and_repair(list: &mut TaskList, settings: &Settings) {
+    assign_unique_ids(list);
+    prune_missing_blockers(list);
+    break_dependency_cycles(list);
+    normalize_due_dates(list, settings.date_dialect);
+    bump_overdue_recurrences(
+        list,
+        settings.overdue_recurrence,
+        Local::now().naive_local(),
+    );
+}
+
+/// Moves the due dates of open recurring tasks that are already past, per
+/// `policy`: one occurrence on, or on to the first one still to come, so
+/// missed days don't pile up as overdue tasks.
+fn bump_overdue_recurrences(list: &mut TaskList, policy: OverdueRecurrence, now: NaiveDateTime) {
+    if policy == OverdueRecurrence::Off {
+        return;
+    }
+    let mut bumped = 0;
+    for task in list.tasks.iter_mut() {
+        bumped += usize::from(bump_overdue_recurrence(task, policy, now));
+        for sub_task in task.sub_tasks.iter_mut() {
+            bumped += usize::from(bump_overdue_recurrence(sub_task, policy, now));
+        }
+    }
+    if bumped > 0 {
+        list.repairs.push(format!(
+            "Moved {} overdue recurring task(s) to a later occurrence",
+            bumped
+        ));
+    }
+}
+
+/// Applies `policy` to one task; `true` when its due date moved.
+fn bump_overdue_recurrence(task: &mut Task, policy: OverdueRecurrence, now: NaiveDateTime) -> bool {
+    let (Some(recurrence), Some(due_date)) = (task.recurrence, &task.due_date) else {
+        return false;
+    };
+    if task.is_done() {
+        return false;
+    }
+    let mut due_date = due_date.clone();
+    // Bounded, so a far-past date can't keep the load busy
+    for _ in 0..10_000 {
+        if due_deadline(&due_date).is_none_or(|deadline| deadline >= now) {
+            break;
+        }
+        let Some(next) = advance_due_date(&due_date, recurrence) else {
+            break;
+        };
+        due_date = next;
+        if policy == OverdueRecurrence::CatchUp {
+            break;
+        }
+    }
+    if task.due_date.as_ref() == Some(&due_date) {
+        return false;
+    }
+    task.due_date = Some(due_date);
+    true
+}
+
+/// Rewrites due dates stored in other shapes into the standard one, so they
+/// sort and compare correctly. One left unreadable is kept as written.
+fn normalize_due_dates(list: &mut TaskList, dialect: DateDialect) {
+    let now = Local::now();
+    let mut rewritten = 0;
+    for task in list.tasks.iter_mut() {
+        let parent = task.description.clone();
+        normalize_task_due_date(task, None, now, dialect, &mut rewritten, &mut list.repairs);
+        for sub_task in task.sub_tasks.iter_mut() {
+            normalize_task_due_date(
+                sub_task,
+                Some(&parent),
+                now,
+                dialect,
+                &mut rewritten,
+                &mut list.repairs,
+            );
+        }
+    }
+    if rewritten > 0 {
+        list.repairs.push(format!(
+            "Rewrote {} due date(s) in the standard YYYY-MM-DD [HH:MM] form",
+            rewritten
+        ));
+    }
+}
+
+fn normalize_task_due_date(
+    task: &mut Task,
+    parent: Option<&str>,
+    now: DateTime<Local>,
+    dialect: DateDialect,
+    rewritten: &mut usize,
+    repairs: &mut Vec<String>,
+) {
+    let Some(due_date) = &task.due_date else {
+        return;
+    };
+    match normalize_due_date(due_date, now, dialect) {
+        Some(normalized) if normalized == *due_date => {}
+        Some(normalized) => {
+            task.due_date = Some(normalized);
+            *rewritten += 1;
+        }
+        None => {
+            let owner = match parent {
+                Some(parent) => format!("subtask '{}' of '{}'", task.description, parent),
+                None => format!("'{}'", task.description),
+            };
+            repairs.push(format!(
+                "Couldn't read the due date '{}' of {}; it was left as is",
+                due_date, owner
+            ));
+        }
+    }
+}
+
+/// Older files assigned IDs as `max + 1`, and hand-merged files can repeat
+/// them, which would make ID lookups hit the wrong task. The first task with
+/// an ID keeps it (so `blocked_by` references stay put) and later duplicates
+/// get fresh ones; subtask IDs are made unique within their parent.
+fn assign_unique_ids(list: &mut TaskList) {
+    let max_id = list.tasks.iter().map(|t| t.id).max().unwrap_or(0);
+    // Version 1 files have no counter, so renumbering there isn't news
+    let counted = list.next_id > 0;
+    list.next_id = list.next_id.max(max_id + 1);
+
+    let mut seen = HashSet::new();
+    for task in list.tasks.iter_mut() {
+        if !seen.insert(task.id) {
+            if counted {
+                list.repairs.push(format!(
+                    "'{}' shared ID {} with another task and is now {}",
+                    task.description, task.id, list.next_id
+                ));
+            }
+            task.id = list.next_id;
+            list.next_id += 1;
+        }
+
+        let mut next_subtask_id = task.sub_tasks.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        let mut seen_subtasks = HashSet::new();
+        for sub_task in task.sub_tasks.iter_mut() {
+            if !seen_subtasks.insert(sub_task.id) {
+                if counted {
+                    list.repairs.push(format!(
+                        "Subtask '{}' of '{}' shared ID {} and is now {}",
+                        sub_task.description, task.description, sub_task.id, next_subtask_id
+                    ));
+                }
+                sub_task.id = next_subtask_id;
+                next_subtask_id += 1;
+            }
+        }
+    }
+}
+
+fn prune_missing_blockers(list: &mut TaskList) {
+    let ids: HashSet<usize> = list.tasks.iter().map(|t| t.id).collect();
+    for task in list.tasks.iter_mut() {
+        let before = task.blocked_by.len();
+        let mut kept = HashSet::new();
+        let own_id = task.id;
+        task.blocked_by
+            .retain(|id| *id != own_id && ids.contains(id) && kept.insert(*id));
+        let removed = before - task.blocked_by.len();
+        if removed > 0 {
+            list.repairs.push(format!(
+                "Dropped {} dependency(s) of '{}' on itself or missing tasks",
+                removed, task.description
+            ));
+        }
+    }
+}
+
+/// Removes one dependency from each cycle: the one that closes it when the
+/// tasks are walked in file order.
+fn break_dependency_cycles(list: &mut TaskList) {
+    while let Some((task_id, blocker_id)) = find_cycle_edge(&list.tasks) {
+        let description = |id: usize| {
+            list.tasks
+                .iter()
+                .find(|t| t.id == id)
+                .map(|t| t.description.clone())
+                .unwrap_or_default()
+        };
+        let message = format!(
+            "'{}' no longer waits on '{}', which closed a dependency cycle",
+            description(task_id),
+            description(blocker_id)
+        );
+        list.repairs.push(message);
+        if let Some(task) = list.tasks.iter_mut().find(|t| t.id == task_id) {
+            task.blocked_by.retain(|id| *id != blocker_id);
+        }
+    }
+}
+
+/// A `(task, blocker)` dependency that closes a cycle, if there is one.
+fn find_cycle_edge(tasks: &[Task]) -> Option<(usize, usize)> {
+    let blockers: HashMap<usize, &[usize]> = tasks
+        .iter()
+        .map(|t| (t.id, t.blocked_by.as_slice()))
+        .collect();
+    // `false` while a task is on the current path, `true` once finished
+    let mut visited: HashMap<usize, bool> = HashMap::new();
+
+    fn visit(
+        id: usize,
+        blockers: &HashMap<usize, &[usize]>,
+        visited: &mut HashMap<usize, bool>,
+    ) -> Option<(usize, usize)> {
+        visited.insert(id, false);
+        for &blocker in blockers.get(&id).copied().unwrap_or_default() {
+            match visited.get(&blocker) {
+                Some(false) => return Some((id, blocker)),
+                Some(true) => {}
+                None => {
+                    if let Some(edge) = visit(blocker, blockers, visited) {
+                        return Some(edge);
+                    }
+                }
+            }
+        }
+        visited.insert(id, true);
+        None
+    }
+
+    tasks.iter().find_map(|task| {
+        if visited.contains_key(&task.id) {
+            None
+        } else {
+            visit(task.id, &blockers, &mut visited)
+        }
+    })
+}
+
+/// Reads a task template: a single task (optionally with subtasks) in the
+/// same JSON shape as the tasks file, where only `description` is required.
+/// Templates written with the old `completed` flag are still accepted.
+pub fn load_template<P: AsRef<Path>>(path: P) -> io::Result<Task> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut value: serde_json::Value = serde_json::from_reader(reader).map_err(io::Error::other)?;
+    migrate_completed_flag(&mut value);
+    serde_json::from_value(value).map_err(io::Error::other)
+}
+
+/// Saves atomically: the tasks are written to a sibling temp file which is then
+/// renamed over the target, so a crash mid-write never leaves a truncated file.
+pub fn save_tasks<P: AsRef<Path>>(path: P, tasks: &[Task], next_id: usize) -> io::Result<()> {
+    let path = path.as_ref();
+    FileFormat::from_path(path).check_supported()?;
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
     let file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
-        .open(path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, tasks)?;
-    Ok(())
+        .open(tmp_path)?;
+    let mut writer = BufWriter::new(file);
+    let file = TasksFile {
+        version: TASKS_FILE_VERSION,
+        next_id,
+        tasks,
+    };
+    serde_json::to_writer_pretty(&mut writer, &file)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A file path in a scratch directory unique to this test.
+    fn scratch_file(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo-task-tests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn loads_a_version_1_bare_array() {
+        let path = scratch_file("v1.json");
+        let legacy = json!([
+            {"id": 1, "description": "buy milk", "completed": true, "sub_tasks": [
+                {"id": 1, "description": "skimmed", "completed": false}
+            ]},
+            {"id": 2, "description": "walk dog", "completed": false}
+        ]);
+        fs::write(&path, legacy.to_string()).unwrap();
+
+        let list = load_tasks(&path, &Settings::default()).unwrap();
+
+        assert_eq!(list.tasks.len(), 2);
+        assert_eq!(list.tasks[0].status, Status::Done);
+        assert_eq!(list.tasks[0].sub_tasks[0].status, Status::Todo);
+        assert_eq!(list.tasks[1].status, Status::Todo);
+        assert_eq!(list.next_id, 3);
+        // Renumbering a file without a counter isn't reported
+        assert!(list.repairs.is_empty());
+    }
+
+    #[test]
+    fn loads_a_version_2_envelope() {
+        let list = migrate_tasks(json!({
+            "version": 2,
+            "tasks": [{"id": 4, "description": "file taxes", "completed": true}]
+        }))
+        .unwrap();
+        assert_eq!(list.tasks[0].description, "file taxes");
+        assert_eq!(list.tasks[0].status, Status::Done);
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let path = scratch_file("current.json");
+        let mut task: Task =
+            serde_json::from_value(json!({"id": 7, "description": "plan trip"})).unwrap();
+        task.status = Status::InProgress;
+        task.due_date = Some("2026-10-20 09:00".to_string());
+        save_tasks(&path, &[task], 8).unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], TASKS_FILE_VERSION);
+        let list = load_tasks(&path, &Settings::default()).unwrap();
+        assert_eq!(list.next_id, 8);
+        assert_eq!(list.tasks[0].status, Status::InProgress);
+        assert_eq!(list.tasks[0].due_date.as_deref(), Some("2026-10-20 09:00"));
+    }
+
+    #[test]
+    fn refuses_files_from_a_newer_version() {
+        let error = migrate_tasks(json!({"version": TASKS_FILE_VERSION + 1, "tasks": []}))
+            .err()
+            .unwrap();
+        assert!(error.contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn refuses_toml_and_ron_files() {
+        for name in ["tasks.toml", "tasks.ron"] {
+            let path = scratch_file(name);
+            fs::write(&path, "").unwrap();
+            let error = load_tasks(&path, &Settings::default()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::Unsupported, "{}", name);
+            assert!(error.to_string().contains(".json"), "{}", error);
+            let error = save_tasks(&path, &[], 1).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::Unsupported, "{}", name);
+            // The file that was there is left alone
+            assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        }
+    }
+
+    #[test]
+    fn messy_due_dates_are_normalized_on_load() {
+        let path = scratch_file("messy.json");
+        let due_dates = [
+            "2026-10-20T09:00:00",
+            "2026/10/20 17:30",
+            "2026-10-20 08:15:00",
+            "2026/10/20",
+            "20 Oct 2026",
+            "October 20, 2026",
+            "2026-10-20",
+            "whenever",
+        ];
+        let tasks: Vec<_> = due_dates
+            .iter()
+            .enumerate()
+            .map(|(i, due)| json!({"id": i + 1, "description": format!("task {}", i), "due_date": due}))
+            .collect();
+        fs::write(&path, json!({"version": 4, "tasks": tasks}).to_string()).unwrap();
+
+        let list = load_tasks(&path, &Settings::default()).unwrap();
+        let loaded: Vec<_> = list
+            .tasks
+            .iter()
+            .map(|t| t.due_date.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            loaded,
+            [
+                "2026-10-20 09:00",
+                "2026-10-20 17:30",
+                "2026-10-20 08:15",
+                "2026-10-20",
+                "2026-10-20",
+                "2026-10-20",
+                "2026-10-20",
+                "whenever",
+            ]
+        );
+        assert!(
+            list.repairs
+                .iter()
+                .any(|r| r.contains("Rewrote 6 due date(s)")),
+            "{:?}",
+            list.repairs
+        );
+        assert!(
+            list.repairs.iter().any(|r| r.contains("'whenever'")),
+            "{:?}",
+            list.repairs
+        );
+    }
+
+    #[test]
+    fn numeric_due_dates_follow_the_dialect() {
+        let now = Local::now();
+        assert_eq!(
+            normalize_due_date("1/6/2026", now, DateDialect::Us).as_deref(),
+            Some("2026-01-06")
+        );
+        assert_eq!(
+            normalize_due_date("1/6/2026", now, DateDialect::Uk).as_deref(),
+            Some("2026-06-01")
+        );
+    }
+
+    #[test]
+    fn recurrences_parse_and_step() {
+        assert_eq!(Recurrence::parse("Weekly"), Some(Recurrence::Weekly));
+        assert_eq!(Recurrence::parse("every 3 days"), Some(Recurrence::Days(3)));
+        assert_eq!(Recurrence::parse("3d"), Some(Recurrence::Days(3)));
+        assert_eq!(Recurrence::parse("0d"), None);
+        assert_eq!(Recurrence::parse("every day"), Some(Recurrence::Daily));
+        assert_eq!(Recurrence::parse("every week"), Some(Recurrence::Weekly));
+        assert_eq!(Recurrence::parse("Every Month"), Some(Recurrence::Monthly));
+        assert_eq!(Recurrence::parse("every 1 day"), Some(Recurrence::Daily));
+        assert_eq!(Recurrence::parse("every 2 days"), Some(Recurrence::Days(2)));
+        assert_eq!(Recurrence::parse("3"), None);
+        assert_eq!(Recurrence::parse("every"), None);
+        assert_eq!(Recurrence::parse("sometimes"), None);
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            Recurrence::Monthly.next_after(date(2026, 1, 31)),
+            date(2026, 2, 28)
+        );
+        assert_eq!(
+            Recurrence::Yearly.next_after(date(2028, 2, 29)),
+            date(2029, 2, 28)
+        );
+        assert_eq!(
+            advance_due_date("2026-10-14 09:30", Recurrence::Weekly).as_deref(),
+            Some("2026-10-21 09:30")
+        );
+        assert_eq!(
+            advance_due_date("2026-10-14", Recurrence::Days(3)).as_deref(),
+            Some("2026-10-17")
+        );
+    }
+
+    /// A daily task due at 9:00 on the 10th, loaded on the 14th at 10:00
+    /// under `policy`; returns its due date afterwards and the repairs.
+    fn load_overdue_daily(policy: OverdueRecurrence, due_date: &str) -> (String, Vec<String>) {
+        let mut task: Task =
+            serde_json::from_value(json!({"id": 1, "description": "water plants"})).unwrap();
+        task.due_date = Some(due_date.to_string());
+        task.recurrence = Some(Recurrence::Daily);
+        let mut list = TaskList {
+            tasks: vec![task],
+            next_id: 2,
+            repairs: Vec::new(),
+        };
+        let now = NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        bump_overdue_recurrences(&mut list, policy, now);
+        (list.tasks[0].due_date.clone().unwrap(), list.repairs)
+    }
+
+    #[test]
+    fn overdue_daily_task_catches_up_one_step() {
+        let (due_date, repairs) =
+            load_overdue_daily(OverdueRecurrence::CatchUp, "2026-10-10 09:00");
+        assert_eq!(due_date, "2026-10-11 09:00");
+        assert_eq!(
+            repairs,
+            ["Moved 1 overdue recurring task(s) to a later occurrence"]
+        );
+    }
+
+    #[test]
+    fn overdue_daily_task_skips_to_the_next_future_occurrence() {
+        let (due_date, _) = load_overdue_daily(OverdueRecurrence::Skip, "2026-10-10 09:00");
+        // Today's 9:00 has passed too
+        assert_eq!(due_date, "2026-10-15 09:00");
+        // A date-only occurrence lasts all day, so today's still counts
+        let (due_date, _) = load_overdue_daily(OverdueRecurrence::Skip, "2026-10-10");
+        assert_eq!(due_date, "2026-10-14");
+    }
+
+    #[test]
+    fn overdue_recurrences_are_left_alone_when_off() {
+        let (due_date, repairs) = load_overdue_daily(OverdueRecurrence::Off, "2026-10-10 09:00");
+        assert_eq!(due_date, "2026-10-10 09:00");
+        assert!(repairs.is_empty());
+    }
 }
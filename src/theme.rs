@@ -1,7 +1,8 @@
+use crate::config::config_dir;
 use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializableColor {
@@ -29,27 +30,53 @@ impl From<SerializableColor> for Color {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A theme as stored in a theme file. With a `base` theme any color left
+/// out is taken from it, so a file only needs the colors it changes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SerializableTheme {
-    pub name: String,
-    pub background: SerializableColor,
-    pub foreground: SerializableColor,
-    pub primary: SerializableColor,
-    pub secondary: SerializableColor,
-    pub accent: SerializableColor,
-    pub surface0: SerializableColor,
-    pub surface1: SerializableColor,
-    pub surface2: SerializableColor,
-    pub text: SerializableColor,
-    pub subtext: SerializableColor,
-    pub red: SerializableColor,
-    pub yellow: SerializableColor,
-    pub green: SerializableColor,
-    pub blue: SerializableColor,
-    pub mauve: SerializableColor,
-    pub lavender: SerializableColor,
+    /// Key of the built-in or custom theme this one extends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    pub name: Option<String>,
+    pub background: Option<SerializableColor>,
+    pub foreground: Option<SerializableColor>,
+    pub primary: Option<SerializableColor>,
+    pub secondary: Option<SerializableColor>,
+    pub accent: Option<SerializableColor>,
+    pub surface0: Option<SerializableColor>,
+    pub surface1: Option<SerializableColor>,
+    pub surface2: Option<SerializableColor>,
+    pub text: Option<SerializableColor>,
+    pub subtext: Option<SerializableColor>,
+    pub red: Option<SerializableColor>,
+    pub yellow: Option<SerializableColor>,
+    pub green: Option<SerializableColor>,
+    pub blue: Option<SerializableColor>,
+    pub mauve: Option<SerializableColor>,
+    pub lavender: Option<SerializableColor>,
 }
 
+/// The color fields of a theme; a file without a `base` must define them all.
+pub const COLOR_FIELDS: [&str; 16] = [
+    "background",
+    "foreground",
+    "primary",
+    "secondary",
+    "accent",
+    "surface0",
+    "surface1",
+    "surface2",
+    "text",
+    "subtext",
+    "red",
+    "yellow",
+    "green",
+    "blue",
+    "mauve",
+    "lavender",
+];
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub name: String,
@@ -71,26 +98,31 @@ pub struct Theme {
     pub lavender: Color,
 }
 
-impl From<SerializableTheme> for Theme {
-    fn from(theme: SerializableTheme) -> Self {
+impl SerializableTheme {
+    /// The full theme: this file's colors, with `base` filling in the rest.
+    /// `default_name` is used when the file doesn't name the theme.
+    pub fn merge_onto(self, base: &Theme, default_name: &str) -> Theme {
+        let pick = |color: Option<SerializableColor>, fallback: Color| {
+            color.map(Color::from).unwrap_or(fallback)
+        };
         Theme {
-            name: theme.name,
-            background: theme.background.into(),
-            foreground: theme.foreground.into(),
-            primary: theme.primary.into(),
-            secondary: theme.secondary.into(),
-            accent: theme.accent.into(),
-            surface0: theme.surface0.into(),
-            surface1: theme.surface1.into(),
-            surface2: theme.surface2.into(),
-            text: theme.text.into(),
-            subtext: theme.subtext.into(),
-            red: theme.red.into(),
-            yellow: theme.yellow.into(),
-            green: theme.green.into(),
-            blue: theme.blue.into(),
-            mauve: theme.mauve.into(),
-            lavender: theme.lavender.into(),
+            name: self.name.unwrap_or_else(|| default_name.to_string()),
+            background: pick(self.background, base.background),
+            foreground: pick(self.foreground, base.foreground),
+            primary: pick(self.primary, base.primary),
+            secondary: pick(self.secondary, base.secondary),
+            accent: pick(self.accent, base.accent),
+            surface0: pick(self.surface0, base.surface0),
+            surface1: pick(self.surface1, base.surface1),
+            surface2: pick(self.surface2, base.surface2),
+            text: pick(self.text, base.text),
+            subtext: pick(self.subtext, base.subtext),
+            red: pick(self.red, base.red),
+            yellow: pick(self.yellow, base.yellow),
+            green: pick(self.green, base.green),
+            blue: pick(self.blue, base.blue),
+            mauve: pick(self.mauve, base.mauve),
+            lavender: pick(self.lavender, base.lavender),
         }
     }
 }
@@ -98,23 +130,24 @@ impl From<SerializableTheme> for Theme {
 impl From<Theme> for SerializableTheme {
     fn from(theme: Theme) -> Self {
         SerializableTheme {
-            name: theme.name,
-            background: theme.background.into(),
-            foreground: theme.foreground.into(),
-            primary: theme.primary.into(),
-            secondary: theme.secondary.into(),
-            accent: theme.accent.into(),
-            surface0: theme.surface0.into(),
-            surface1: theme.surface1.into(),
-            surface2: theme.surface2.into(),
-            text: theme.text.into(),
-            subtext: theme.subtext.into(),
-            red: theme.red.into(),
-            yellow: theme.yellow.into(),
-            green: theme.green.into(),
-            blue: theme.blue.into(),
-            mauve: theme.mauve.into(),
-            lavender: theme.lavender.into(),
+            base: None,
+            name: Some(theme.name),
+            background: Some(theme.background.into()),
+            foreground: Some(theme.foreground.into()),
+            primary: Some(theme.primary.into()),
+            secondary: Some(theme.secondary.into()),
+            accent: Some(theme.accent.into()),
+            surface0: Some(theme.surface0.into()),
+            surface1: Some(theme.surface1.into()),
+            surface2: Some(theme.surface2.into()),
+            text: Some(theme.text.into()),
+            subtext: Some(theme.subtext.into()),
+            red: Some(theme.red.into()),
+            yellow: Some(theme.yellow.into()),
+            green: Some(theme.green.into()),
+            blue: Some(theme.blue.into()),
+            mauve: Some(theme.mauve.into()),
+            lavender: Some(theme.lavender.into()),
         }
     }
 }
@@ -236,6 +269,96 @@ impl Theme {
         }
     }
 
+    /// Leaves every color to the terminal's defaults, for `NO_COLOR` and
+    /// terminals without color. The list tells things apart with bold,
+    /// reverse video and the status and priority symbols instead.
+    pub fn monochrome() -> Self {
+        Theme {
+            name: "Monochrome".to_string(),
+            background: Color::Reset,
+            foreground: Color::Reset,
+            primary: Color::Reset,
+            secondary: Color::Reset,
+            accent: Color::Reset,
+            surface0: Color::Reset,
+            surface1: Color::Reset,
+            surface2: Color::Reset,
+            text: Color::Reset,
+            subtext: Color::Reset,
+            red: Color::Reset,
+            yellow: Color::Reset,
+            green: Color::Reset,
+            blue: Color::Reset,
+            mauve: Color::Reset,
+            lavender: Color::Reset,
+        }
+    }
+
+    /// Whether the theme sets no colors at all, so styling has to come from
+    /// modifiers.
+    pub fn is_monochrome(&self) -> bool {
+        self.colors().iter().all(|color| *color == Color::Reset)
+    }
+
+    /// The colors in `COLOR_FIELDS` order, for the theme editor.
+    pub fn colors(&self) -> [Color; 16] {
+        [
+            self.background,
+            self.foreground,
+            self.primary,
+            self.secondary,
+            self.accent,
+            self.surface0,
+            self.surface1,
+            self.surface2,
+            self.text,
+            self.subtext,
+            self.red,
+            self.yellow,
+            self.green,
+            self.blue,
+            self.mauve,
+            self.lavender,
+        ]
+    }
+
+    /// The color at `index` in `COLOR_FIELDS` order.
+    pub fn color_mut(&mut self, index: usize) -> Option<&mut Color> {
+        Some(match index {
+            0 => &mut self.background,
+            1 => &mut self.foreground,
+            2 => &mut self.primary,
+            3 => &mut self.secondary,
+            4 => &mut self.accent,
+            5 => &mut self.surface0,
+            6 => &mut self.surface1,
+            7 => &mut self.surface2,
+            8 => &mut self.text,
+            9 => &mut self.subtext,
+            10 => &mut self.red,
+            11 => &mut self.yellow,
+            12 => &mut self.green,
+            13 => &mut self.blue,
+            14 => &mut self.mauve,
+            15 => &mut self.lavender,
+            _ => return None,
+        })
+    }
+
+    /// Named accent colors offered when picking a per-task color
+    pub fn palette(&self) -> Vec<(&'static str, Color)> {
+        vec![
+            ("Red", self.red),
+            ("Yellow", self.yellow),
+            ("Green", self.green),
+            ("Blue", self.blue),
+            ("Mauve", self.mauve),
+            ("Lavender", self.lavender),
+            ("Accent", self.accent),
+            ("Primary", self.primary),
+        ]
+    }
+
     pub fn get_builtin_themes() -> HashMap<String, Theme> {
         let mut themes = HashMap::new();
 
@@ -244,6 +367,7 @@ impl Theme {
         themes.insert("dracula".to_string(), Self::dracula());
         themes.insert("gruvbox-dark".to_string(), Self::gruvbox_dark());
         themes.insert("nord".to_string(), Self::nord());
+        themes.insert("monochrome".to_string(), Self::monochrome());
 
         themes
     }
@@ -280,12 +404,81 @@ impl ThemeManager {
         &self.current_theme
     }
 
+    /// The theme in use, for editing. It is a copy, so changes leave the
+    /// built-in and loaded themes untouched until saved.
+    pub fn current_theme_mut(&mut self) -> &mut Theme {
+        &mut self.current_theme
+    }
+
+    /// Writes the current theme to `themes/<key>.json` in the config
+    /// directory and makes it available under `key`.
+    pub fn save_current_theme(&mut self, key: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let themes_dir = config_dir()?.join("themes");
+        std::fs::create_dir_all(&themes_dir)?;
+        let path = themes_dir.join(format!("{}.json", key));
+        let theme = SerializableTheme::from(self.current_theme.clone());
+        std::fs::write(&path, serde_json::to_string_pretty(&theme)?)?;
+        self.available_themes
+            .insert(key.to_string(), self.current_theme.clone());
+        Ok(path)
+    }
+
+    /// Switches to the theme `theme_name` refers to; see `resolve_theme_key`.
     pub fn set_theme(&mut self, theme_name: &str) -> Result<(), String> {
-        if let Some(theme) = self.available_themes.get(theme_name) {
-            self.current_theme = theme.clone();
-            Ok(())
+        let key = self.resolve_theme_key(theme_name)?;
+        self.current_theme = self.available_themes[&key].clone();
+        Ok(())
+    }
+
+    /// Finds the theme key a typed name means: the exact key, else a key or
+    /// display name equal to it ignoring case, else the one key with a word
+    /// starting with it ("mocha", "drac") or, failing that, containing it.
+    /// Several matches are an error listing them.
+    pub fn resolve_theme_key(&self, theme_name: &str) -> Result<String, String> {
+        if self.available_themes.contains_key(theme_name) {
+            return Ok(theme_name.to_string());
+        }
+        let query = theme_name.trim().to_lowercase();
+        let mut keys: Vec<&String> = self.available_themes.keys().collect();
+        keys.sort();
+        if let Some(key) = keys.iter().find(|key| {
+            key.to_lowercase() == query || self.available_themes[**key].name.to_lowercase() == query
+        }) {
+            return Ok(key.to_string());
+        }
+        if query.is_empty() {
+            return Err("No theme name given".to_string());
+        }
+
+        let prefixed: Vec<&String> = keys
+            .iter()
+            .copied()
+            .filter(|key| {
+                key.to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|word| word.starts_with(&query))
+            })
+            .collect();
+        let candidates = if prefixed.is_empty() {
+            keys.iter()
+                .copied()
+                .filter(|key| key.to_lowercase().contains(&query))
+                .collect()
         } else {
-            Err(format!("Theme '{}' not found", theme_name))
+            prefixed
+        };
+        match candidates[..] {
+            [key] => Ok(key.clone()),
+            [] => Err(format!("Theme '{}' not found", theme_name)),
+            _ => Err(format!(
+                "Theme '{}' is ambiguous: {}",
+                theme_name,
+                candidates
+                    .iter()
+                    .map(|key| key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
         }
     }
 
@@ -297,59 +490,233 @@ impl ThemeManager {
         self.available_themes.get(key)
     }
 
+    /// Loads the theme files in the config directory, merging each one onto
+    /// its base theme. Files with problems are skipped with a warning naming
+    /// each bad field.
     pub fn load_custom_themes(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        use std::fs;
-
-        // Create config directory if it doesn't exist
-        let config_dir = self.get_config_dir()?;
-        let themes_dir = config_dir.join("themes");
-
-        if !themes_dir.exists() {
-            fs::create_dir_all(&themes_dir)?;
-            return Ok(());
-        }
-
-        // Load all .json files from themes directory
-        for entry in fs::read_dir(&themes_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                match fs::read_to_string(&path) {
-                    Ok(content) => match serde_json::from_str::<SerializableTheme>(&content) {
-                        Ok(serializable_theme) => {
-                            let theme = Theme::from(serializable_theme);
-                            let theme_name = path
-                                .file_stem()
-                                .and_then(|s| s.to_str())
-                                .unwrap_or("custom")
-                                .to_string();
-                            self.available_themes.insert(theme_name, theme);
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Failed to parse theme file {:?}: {}", path, e);
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("Warning: Failed to read theme file {:?}: {}", path, e);
-                    }
+        let files = theme_files()?;
+        let known = known_theme_keys(&files);
+        let mut pending = Vec::new();
+        for path in files {
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Warning: Failed to read theme file {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            let problems = validate_theme(&content, &known);
+            if !problems.is_empty() {
+                eprintln!("Warning: Skipping theme file {:?}:", path);
+                for problem in problems {
+                    eprintln!("  {}", problem);
+                }
+                continue;
+            }
+            match serde_json::from_str::<SerializableTheme>(&content) {
+                Ok(serializable_theme) => pending.push((path, serializable_theme)),
+                Err(e) => {
+                    eprintln!("Warning: Failed to parse theme file {:?}: {}", path, e);
                 }
             }
         }
 
+        // A custom theme can extend another one, so keep merging whichever
+        // theme's base is already available until none are left
+        while let Some(index) = pending.iter().position(|(_, theme)| {
+            theme
+                .base
+                .as_ref()
+                .is_none_or(|base| self.available_themes.contains_key(base))
+        }) {
+            let (path, theme) = pending.remove(index);
+            let base = theme
+                .base
+                .as_ref()
+                .and_then(|base| self.available_themes.get(base))
+                .cloned()
+                .unwrap_or_default();
+            let key = theme_key(&path);
+            let theme = theme.merge_onto(&base, &key);
+            self.available_themes.insert(key, theme);
+        }
+        for (path, theme) in pending {
+            eprintln!(
+                "Warning: Skipping theme file {:?}: base theme '{}' could not be loaded",
+                path,
+                theme.base.unwrap_or_default()
+            );
+        }
+
         Ok(())
     }
+}
 
-    fn get_config_dir(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
-        if let Some(config_dir) = dirs::config_dir() {
-            Ok(config_dir.join("todo"))
-        } else {
-            // Fallback to home directory
-            if let Some(home_dir) = dirs::home_dir() {
-                Ok(home_dir.join(".config").join("todo"))
-            } else {
-                Err("Could not determine config directory".into())
-            }
+/// Checks a theme file field by field, describing each problem with the line
+/// it is on where that can be found. `known` lists the theme keys a `base`
+/// may name. Empty when the theme loads.
+pub fn validate_theme(content: &str, known: &[String]) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => return vec![format!("invalid JSON: {}", e)],
+    };
+    let Some(fields) = value.as_object() else {
+        return vec!["expected an object with a name and colors".to_string()];
+    };
+
+    let mut problems = Vec::new();
+    if fields.get("name").is_some_and(|name| !name.is_string()) {
+        problems.push(format!(
+            "{}`name` should be a string",
+            line_prefix(content, "name")
+        ));
+    }
+    let has_base = match fields.get("base") {
+        None => false,
+        Some(serde_json::Value::String(base)) if known.contains(base) => true,
+        Some(serde_json::Value::String(base)) => {
+            problems.push(format!(
+                "{}base theme '{}' not found (available: {})",
+                line_prefix(content, "base"),
+                base,
+                known.join(", ")
+            ));
+            true
+        }
+        Some(_) => {
+            problems.push(format!(
+                "{}`base` should be the name of a theme",
+                line_prefix(content, "base")
+            ));
+            true
+        }
+    };
+    for field in COLOR_FIELDS {
+        match fields.get(field) {
+            None if has_base => {}
+            None => problems.push(format!("missing color `{}` (or add a \"base\" theme)", field)),
+            Some(color) if !is_valid_color(color) => problems.push(format!(
+                "{}`{}` should look like {{\"r\": 0, \"g\": 128, \"b\": 255}} with values 0-255, found {}",
+                line_prefix(content, field),
+                field,
+                color
+            )),
+            Some(_) => {}
+        }
+    }
+    for field in fields.keys() {
+        if field != "name" && field != "base" && !COLOR_FIELDS.contains(&field.as_str()) {
+            problems.push(format!(
+                "{}unknown field `{}`",
+                line_prefix(content, field),
+                field
+            ));
+        }
+    }
+    problems
+}
+
+fn is_valid_color(color: &serde_json::Value) -> bool {
+    color.as_object().is_some_and(|channels| {
+        channels.len() == 3
+            && ["r", "g", "b"].iter().all(|channel| {
+                channels
+                    .get(*channel)
+                    .and_then(|value| value.as_u64())
+                    .is_some_and(|value| value <= 255)
+            })
+    })
+}
+
+/// "line N: " for the first line defining `key`, or nothing if not found.
+fn line_prefix(content: &str, key: &str) -> String {
+    let quoted = format!("\"{}\"", key);
+    content
+        .lines()
+        .position(|line| line.contains(&quoted))
+        .map(|index| format!("line {}: ", index + 1))
+        .unwrap_or_default()
+}
+
+/// The `.json` files in the themes config directory, creating it if missing.
+fn theme_files() -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let themes_dir = config_dir()?.join("themes");
+    if !themes_dir.exists() {
+        std::fs::create_dir_all(&themes_dir)?;
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&themes_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            files.push(path);
         }
     }
+    files.sort();
+    Ok(files)
+}
+
+/// A theme file and the problems found in it.
+pub type ThemeReport = (PathBuf, Vec<String>);
+
+/// Validates every custom theme file, returning each one with its problems
+/// (empty for files that load).
+pub fn validate_theme_files() -> Result<Vec<ThemeReport>, Box<dyn std::error::Error>> {
+    let files = theme_files()?;
+    let known = known_theme_keys(&files);
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let problems = match std::fs::read_to_string(&path) {
+                Ok(content) => validate_theme(&content, &known),
+                Err(e) => vec![format!("could not read the file: {}", e)],
+            };
+            (path, problems)
+        })
+        .collect())
+}
+
+/// Keys a theme file may use as its base: the built-in themes and the other
+/// custom theme files, sorted.
+fn known_theme_keys(files: &[PathBuf]) -> Vec<String> {
+    let mut keys: Vec<String> = Theme::get_builtin_themes().into_keys().collect();
+    keys.extend(files.iter().map(|path| theme_key(path)));
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Theme key for a custom theme file: its name without the extension.
+fn theme_key(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("custom")
+        .to_string()
+}
+
+/// Reads a color typed as `#89dceb`, `89dceb` or `137,220,235`.
+pub fn parse_color(input: &str) -> Option<Color> {
+    let input = input.trim();
+    if input.contains(',') {
+        let channels: Vec<u8> = input
+            .split(',')
+            .map(|channel| channel.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        return match channels[..] {
+            [r, g, b] => Some(Color::Rgb(r, g, b)),
+            _ => None,
+        };
+    }
+    let hex = input.strip_prefix('#').unwrap_or(input);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(&hex[range], 16).ok();
+    Some(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+/// A color as `#rrggbb`, the form the theme editor shows and prefills.
+pub fn color_hex(color: Color) -> String {
+    let SerializableColor { r, g, b } = color.into();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
 }
@@ -1,30 +1,41 @@
-use crate::app::{App, AppMode};
-use crate::task::Priority;
+use crate::action::{help_sections, leader_entries, LeaderNode};
+use crate::app::{find_urls, max_margin, App, AppMode, ConfirmAction, DisplayRow, Level, SortMode};
+use crate::config::{ProgressBar, TimeFormat};
+use crate::task::{
+    due_deadline, due_state, format_duration, DueState, Priority, Status, Task, DUE_DATETIME_FORMAT,
+};
+use crate::theme::{color_hex, Theme, COLOR_FIELDS};
 use chrono::prelude::*;
 use ratatui::{
-
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::{self, bar, border},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        block::{Position, Title},
+        BarChart, Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap,
+    },
     Frame,
 };
 
 pub fn ui(f: &mut Frame, app: &mut App) {
+    app.frame_size = f.size();
     let theme = app.theme_manager.get_current_theme();
 
     // Create a global background
     let background = Block::default().style(Style::default().bg(theme.background));
     f.render_widget(background, f.size());
 
-    // Create a centered viewport based on the margin/zoom level
+    // Create a centered viewport based on the margin/zoom level, clamped in
+    // case the terminal has shrunk since the margin was chosen
+    let margin = app.margin.min(max_margin(f.size()));
     let vertical_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Length(app.margin), // Top padding
-                Constraint::Min(0),             // Content
-                Constraint::Length(app.margin), // Bottom padding
+                Constraint::Length(margin), // Top padding
+                Constraint::Min(0),         // Content
+                Constraint::Length(margin), // Bottom padding
             ]
             .as_ref(),
         )
@@ -34,9 +45,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .direction(Direction::Horizontal)
         .constraints(
             [
-                Constraint::Length(app.margin * 2), // Left padding (x2 for better aspect ratio)
-                Constraint::Min(0),                 // Content
-                Constraint::Length(app.margin * 2), // Right padding
+                Constraint::Length(margin * 2), // Left padding (x2 for better aspect ratio)
+                Constraint::Min(0),             // Content
+                Constraint::Length(margin * 2), // Right padding
             ]
             .as_ref(),
         )
@@ -44,154 +55,254 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     let viewport = horizontal_chunks[1];
 
-    // Render the application within the calculated viewport
+    // Render the application within the calculated viewport, with the
+    // progress gauge (when there is anything to measure) above the footer
+    let progress = match app.settings.progress_bar {
+        ProgressBar::Off => None,
+        ProgressBar::TopLevel => Some(app.completion_progress(false)),
+        ProgressBar::WithSubtasks => Some(app.completion_progress(true)),
+    }
+    .filter(|(_, total)| *total > 0);
     let app_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(progress.is_some() as u16),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
         .split(viewport);
 
-    render_tasks(f, app, app_chunks[0]);
-    render_footer(f, app, app_chunks[1]);
+    // While searching, the search input sits above the list instead of over
+    // it, so the results can be seen narrowing with each keystroke
+    let content = if matches!(app.mode, AppMode::Search) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(app_chunks[0]);
+        render_search_bar(f, app, chunks[0]);
+        chunks[1]
+    } else {
+        app_chunks[0]
+    };
+
+    let list_area = if app.filters.is_empty() {
+        content
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(content);
+        render_filter_bar(f, app, chunks[0]);
+        chunks[1]
+    };
+
+    if app.show_details {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(list_area);
+        render_tasks(f, app, panes[0]);
+        render_detail(f, app, panes[1]);
+    } else {
+        render_tasks(f, app, list_area);
+    }
+    if let Some((done, total)) = progress {
+        render_progress(f, app, app_chunks[1], done, total);
+    }
+    render_footer(f, app, app_chunks[2]);
+
+    if app.leader_keys.is_some() {
+        render_leader_popup(f, app);
+    }
 
     match app.mode {
         AppMode::Insert => render_input_popup(f, app),
         AppMode::DateInput => render_date_input_popup(f, app),
-        AppMode::Search => render_search_popup(f, app),
+        AppMode::EstimateInput => render_estimate_popup(f, app),
+        AppMode::EffortInput => render_effort_popup(f, app),
+        AppMode::RecurrenceInput => render_recurrence_popup(f, app),
+        AppMode::TagInput => render_tag_input_popup(f, app),
+        AppMode::MoveTo => render_move_popup(f, app),
+        AppMode::CommandPalette => render_command_palette(f, app),
+        AppMode::Notes => render_notes_popup(f, app),
         AppMode::Confirm => render_confirm_dialog(f, app),
         AppMode::Help => render_help_dialog(f, app),
+        AppMode::Stats => render_stats_dialog(f, app),
+        AppMode::ColorPicker => render_color_picker(f, app),
+        AppMode::DependencyPicker => render_dependency_picker(f, app),
+        AppMode::PresetPicker => render_preset_picker(f, app),
+        AppMode::ThemeEditor => render_theme_editor(f, app),
         _ => {}
     }
+
+    render_toasts(f, app);
 }
 
-fn render_tasks(f: &mut Frame, app: &mut App, area: Rect) {
+/// Stacks the most recent notifications in the top-right corner, newest
+/// first, colored by level.
+fn render_toasts(f: &mut Frame, app: &App) {
+    const SHOWN: usize = 3;
     let theme = app.theme_manager.get_current_theme();
-    let mut items = Vec::new();
-    let displayed_tasks = app.get_displayed_tasks();
-
-    for task in displayed_tasks.iter() {
-        let (style, symbol) = if task.completed {
-            (
-                Style::default()
-                    .fg(theme.surface2)
-                    .add_modifier(Modifier::CROSSED_OUT),
-                " ✔ ",
-            )
-        } else {
-            (Style::default().fg(theme.text), " ❯ ")
-        };
-        let priority_style = Style::default().fg(match task.priority {
-            Priority::High => theme.red,
-            Priority::Medium => theme.yellow,
-            Priority::Low => theme.green,
-        });
-        let priority_symbol = match task.priority {
-            Priority::High => " ▲",
-            Priority::Medium => " ●",
-            Priority::Low => " ▼",
-        };
-
-        let mut spans = vec![
-            Span::styled(symbol, Style::default().fg(theme.primary)),
-            Span::raw(task.description.clone()),
-            Span::styled(priority_symbol, priority_style),
-        ];
-
-        if let Some(due_date) = &task.due_date {
-            let due_date_style = if Local::now().format("%Y-%m-%d").to_string() > *due_date {
-                Style::default().fg(theme.red)
-            } else {
-                Style::default().fg(theme.subtext)
-            };
-            spans.push(Span::styled(
-                format!(" (due: {})", due_date),
-                due_date_style,
-            ));
+    let size = f.size();
+    let width = size.width.min(50);
+    for (slot, (message, _, level)) in app.messages.iter().rev().take(SHOWN).enumerate() {
+        let y = size.y + 1 + slot as u16 * 3;
+        if y + 3 > size.bottom() {
+            break;
         }
+        let area = Rect::new(size.right().saturating_sub(width + 1), y, width, 3);
+        let (title, color) = match level {
+            Level::Info => (" Info ", theme.blue),
+            Level::Warn => (" Warning ", theme.yellow),
+            Level::Error => (" Error ", theme.red),
+        };
+        let toast = Paragraph::new(Span::styled(
+            message.clone(),
+            Style::default().fg(theme.text),
+        ))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(color))
+                .title(title)
+                .title_style(Style::default().fg(color)),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(toast, area);
+    }
+}
 
-        if !task.tags.is_empty() {
-            spans.push(Span::raw(" "));
-            for tag in task.tags.iter() {
-                spans.push(Span::styled(tag, Style::default().fg(theme.accent)));
-                spans.push(Span::raw(" "));
-            }
-        }
+/// One line of numbered chips, one per active filter; pressing a chip's number
+/// removes it.
+fn render_filter_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme_manager.get_current_theme();
+    let mut spans = Vec::new();
+    for (index, filter) in app.filters.iter().enumerate() {
+        spans.push(Span::styled(
+            format!(" {} {} ", index + 1, filter.label()),
+            Style::default().fg(theme.background).bg(theme.accent),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(
+        "1-9: remove  Backspace: clear all",
+        Style::default().fg(theme.subtext),
+    ));
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
 
-        items.push(ListItem::new(Line::from(spans)).style(style));
+fn render_tasks(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme_manager.get_current_theme();
+    let mut items = Vec::new();
+    let rows = app.display_rows();
+    let search_terms = app.active_search_terms();
 
-        // Only show subtasks if not in focus mode or if the parent task is not completed
-        if !app.focus_mode || !task.completed {
-            for sub_task in task.sub_tasks.iter() {
-                // In focus mode, skip completed subtasks
-                if app.focus_mode && sub_task.completed {
-                    continue;
-                }
+    // Indices follow the displayed order so they match what the user sees
+    let task_count = rows
+        .iter()
+        .filter(|row| matches!(row, DisplayRow::Task { .. }))
+        .count();
+    let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+    let context = LineContext {
+        number_width: task_count.to_string().len(),
+        search_terms,
+        now: Local::now().naive_local(),
+        // Inside the borders, less the column the highlight symbol takes
+        width: (area.width.saturating_sub(2) as usize)
+            .saturating_sub(Span::raw(highlight_symbol.as_str()).width()),
+        due_width: if app.settings.due_column {
+            rows.iter()
+                .filter_map(|row| match row {
+                    DisplayRow::Task { task, .. } | DisplayRow::Subtask { task, .. } => {
+                        task.due_date.as_deref()
+                    }
+                    DisplayRow::Header(_) | DisplayRow::Spacer => None,
+                })
+                .map(|due_date| {
+                    Span::raw(format_due_date(due_date, app.settings.time_format)).width()
+                })
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        },
+    };
 
-                let (style, symbol) = if sub_task.completed {
-                    (
-                        Style::default()
-                            .fg(theme.surface2)
-                            .add_modifier(Modifier::CROSSED_OUT),
-                        " ✔ ",
-                    )
+    for (index, row) in rows.iter().enumerate() {
+        let timing = app
+            .timer
+            .is_some_and(|(task_ref, _)| row.task_ref() == Some(task_ref));
+        let selected = app.state.selected() == Some(index);
+        match row {
+            DisplayRow::Header(label) => {
+                items.push(ListItem::new(Line::from(Span::styled(
+                    format!("   {}", label),
+                    Style::default()
+                        .fg(theme.subtext)
+                        .add_modifier(Modifier::BOLD),
+                ))));
+            }
+            DisplayRow::Task {
+                task,
+                number,
+                context: parent_context,
+            } => {
+                let item =
+                    render_task_line(app, task, 0, Some(*number), timing, selected, &context);
+                // Parents listed only for their matching subtasks are dimmed
+                items.push(if *parent_context {
+                    item.style(Style::default().add_modifier(Modifier::DIM))
                 } else {
-                    (Style::default().fg(theme.text), " ❯ ")
-                };
-                let priority_style = Style::default().fg(match sub_task.priority {
-                    Priority::High => theme.red,
-                    Priority::Medium => theme.yellow,
-                    Priority::Low => theme.green,
+                    item
                 });
-                let priority_symbol = match sub_task.priority {
-                    Priority::High => " ▲",
-                    Priority::Medium => " ●",
-                    Priority::Low => " ▼",
-                };
-
-                let mut spans = vec![
-                    Span::raw("  ↳ "),
-                    Span::styled(symbol, Style::default().fg(theme.primary)),
-                    Span::raw(sub_task.description.clone()),
-                    Span::styled(priority_symbol, priority_style),
-                ];
-
-                if let Some(due_date) = &sub_task.due_date {
-                    let due_date_style = if Local::now().format("%Y-%m-%d").to_string() > *due_date
-                    {
-                        Style::default().fg(theme.red)
-                    } else {
-                        Style::default().fg(theme.subtext)
-                    };
-                    spans.push(Span::styled(
-                        format!(" (due: {})", due_date),
-                        due_date_style,
-                    ));
-                }
-
-                if !sub_task.tags.is_empty() {
-                    spans.push(Span::raw(" "));
-                    for tag in sub_task.tags.iter() {
-                        spans.push(Span::styled(tag, Style::default().fg(theme.accent)));
-                        spans.push(Span::raw(" "));
-                    }
-                }
-
-                items.push(ListItem::new(Line::from(spans)).style(style));
             }
+            DisplayRow::Subtask { task, .. } => {
+                items.push(render_task_line(
+                    app, task, 1, None, timing, selected, &context,
+                ));
+            }
+            DisplayRow::Spacer => items.push(ListItem::new(Line::from(""))),
+        }
+        // While dragging, underline the row the task will land on
+        if let Some(drag) = &app.drag {
+            let Some(item) = items.pop() else { continue };
+            items.push(if drag.target == index {
+                item.style(
+                    Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::UNDERLINED),
+                )
+            } else if matches!(row, DisplayRow::Task { task, .. } if task.id == drag.task_id) {
+                item.style(Style::default().add_modifier(Modifier::DIM))
+            } else {
+                item
+            });
         }
     }
 
+    // Filters are shown as chips above the list rather than in the title
+    let focus_indicator = if app.read_only { " [Read-only]" } else { "" };
+    let sort_indicator = match app.sort_mode {
+        SortMode::Manual => String::new(),
+        mode => format!(" [Sort: {}]", mode.label()),
+    };
     let title = match app.mode {
-        AppMode::Search if !app.search_input.is_empty() => {
-            let focus_indicator = if app.focus_mode { " [Focus]" } else { "" };
-            format!(" To-Do (Search: {}){} ", app.search_input, focus_indicator)
-        }
-        AppMode::Search => {
-            let focus_indicator = if app.focus_mode { " [Focus]" } else { "" };
-            format!(" To-Do (Search Mode){} ", focus_indicator)
-        }
+        AppMode::Search => match app
+            .search_scope
+            .and_then(|id| app.tasks.iter().find(|t| t.id == id))
+        {
+            Some(task) => format!(
+                " To-Do (Search in '{}'){}{} ",
+                task.description, focus_indicator, sort_indicator
+            ),
+            None => format!(" To-Do (Search Mode){}{} ", focus_indicator, sort_indicator),
+        },
         _ => {
-            let focus_indicator = if app.focus_mode { " [Focus]" } else { "" };
-            format!(" To-Do{} ", focus_indicator)
+            format!(" To-Do{}{} ", focus_indicator, sort_indicator)
         }
     };
 
@@ -199,19 +310,663 @@ fn render_tasks(f: &mut Frame, app: &mut App, area: Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .title(title)
                 .border_style(Style::default().fg(theme.surface1))
                 .title_style(Style::default().fg(theme.secondary)),
         )
-        .highlight_style(
+        .highlight_style(selection_style(theme).fg(theme.secondary))
+        .highlight_symbol(&highlight_symbol);
+
+    app.list_area = Block::default().borders(Borders::ALL).inner(area);
+    f.render_stateful_widget(list, area, &mut app.state);
+    app.list_offset = app.state.offset();
+}
+
+/// Shows everything about the selected task or subtask.
+fn render_detail(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme_manager.get_current_theme();
+    let label_style = Style::default()
+        .fg(theme.secondary)
+        .add_modifier(Modifier::BOLD);
+    let text_style = Style::default().fg(theme.text);
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", label), label_style),
+            Span::styled(value, text_style),
+        ])
+    };
+
+    let mut lines = Vec::new();
+    if let Some(task) = app
+        .selected_task_ref()
+        .and_then(|task_ref| app.task(task_ref))
+    {
+        lines.push(Line::from(Span::styled(
+            task.description.clone(),
+            description_style(task).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(""));
+        lines.push(field("Status", task.status.label().to_string()));
+        lines.push(field("Priority", format!("{:?}", task.priority)));
+        if let Some(effort) = task.effort {
+            lines.push(field("Effort", effort.label()));
+        }
+        if let Some(recurrence) = task.recurrence {
+            lines.push(field("Repeats", recurrence.label()));
+        }
+        if let Some(due_date) = &task.due_date {
+            let mut due = format_due_date(due_date, app.settings.time_format);
+            if let Some(relative) = relative_due(due_date) {
+                due.push_str(&format!(" ({})", relative));
+            }
+            lines.push(field("Due", due));
+        }
+        if !task.tags.is_empty() {
+            lines.push(field("Tags", task.tags.join(" ")));
+        }
+        if let Some(label) = time_label(task) {
+            lines.push(field("Time", label));
+        }
+        if !task.sub_tasks.is_empty() {
+            let done = task.sub_tasks.iter().filter(|t| t.is_done()).count();
+            lines.push(field(
+                "Subtasks",
+                format!("{}/{} done", done, task.sub_tasks.len()),
+            ));
+            for sub_task in &task.sub_tasks {
+                let symbol = status_symbol(app, sub_task);
+                lines.push(Line::from(Span::styled(
+                    format!("  {} {}", symbol, sub_task.description),
+                    description_style(sub_task),
+                )));
+            }
+        }
+        if !task.blocked_by.is_empty() {
+            let blockers: Vec<String> = task
+                .blocked_by
+                .iter()
+                .filter_map(|id| app.tasks.iter().find(|t| t.id == *id))
+                .map(|blocker| {
+                    let symbol = if blocker.is_done() {
+                        &app.settings.symbols.check
+                    } else {
+                        &app.settings.symbols.blocked
+                    };
+                    format!("{} {}", symbol, blocker.description)
+                })
+                .collect();
+            lines.push(field("Blocked by", blockers.join(", ")));
+        }
+        lines.push(field(
+            "Created",
+            task.created_at.format("%Y-%m-%d %H:%M").to_string(),
+        ));
+        if let Some(completed_at) = task.completed_at {
+            lines.push(field(
+                "Completed",
+                completed_at.format("%Y-%m-%d %H:%M").to_string(),
+            ));
+        }
+        if let Some(notes) = &task.notes {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Notes:", label_style)));
+            for note_line in notes.lines() {
+                lines.push(Line::from(Span::styled(note_line.to_string(), text_style)));
+            }
+        }
+    } else {
+        lines.push(Line::from(Span::styled(
+            "No task selected",
+            Style::default().fg(theme.subtext),
+        )));
+    }
+
+    let detail = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Details ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.surface1))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, area);
+}
+
+/// Describes how far away a stored due date is, e.g. "in 3 days" or
+/// "2 hours ago". Date-only values are compared by calendar day.
+fn relative_due(due_date: &str) -> Option<String> {
+    let now = Local::now().naive_local();
+    if let Ok(due) = NaiveDateTime::parse_from_str(due_date, DUE_DATETIME_FORMAT) {
+        let delta = due - now;
+        let minutes = delta.num_minutes().abs();
+        let amount = if minutes >= 60 * 24 {
+            plural(minutes / (60 * 24), "day")
+        } else if minutes >= 60 {
+            plural(minutes / 60, "hour")
+        } else {
+            plural(minutes, "minute")
+        };
+        return Some(if delta.num_minutes() < 0 {
+            format!("{} ago", amount)
+        } else {
+            format!("in {}", amount)
+        });
+    }
+
+    let due = NaiveDate::parse_from_str(due_date, "%Y-%m-%d").ok()?;
+    let days = (due - now.date()).num_days();
+    Some(match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        days if days > 0 => format!("in {}", plural(days, "day")),
+        days => format!("{} ago", plural(-days, "day")),
+    })
+}
+
+fn streak_label(app: &App) -> String {
+    format!(
+        "{} {}-day streak",
+        app.settings.symbols.streak,
+        app.current_streak()
+    )
+}
+
+fn plural(count: i64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// What every row of one list render shares.
+struct LineContext {
+    number_width: usize,
+    search_terms: Vec<String>,
+    now: NaiveDateTime,
+    /// Columns available to a row
+    width: usize,
+    /// Width of the widest due date when they are shown in a column
+    due_width: usize,
+}
+
+/// Builds the list row for a task (`depth` 0) or subtask (`depth` 1), so both
+/// get identical completion, priority, due-date and tag styling.
+fn render_task_line(
+    app: &App,
+    task: &Task,
+    depth: usize,
+    number: Option<usize>,
+    timing: bool,
+    selected: bool,
+    context: &LineContext,
+) -> ListItem<'static> {
+    let theme = app.theme_manager.get_current_theme();
+    let symbols = &app.settings.symbols;
+    let blocked = !task.is_done() && app.is_blocked(task);
+    let someday = !task.is_done()
+        && task
+            .due_date
+            .as_deref()
+            .and_then(|due_date| task_due_state(app, due_date, context.now))
+            == Some(DueState::Someday);
+    let (style, symbol) = if task.is_done() {
+        (completed_style(app), &symbols.check)
+    } else if blocked {
+        (
+            Style::default()
+                .fg(theme.subtext)
+                .add_modifier(Modifier::DIM),
+            &symbols.bullet,
+        )
+    } else if task.status == Status::InProgress {
+        (Style::default().fg(theme.yellow), &symbols.in_progress)
+    } else if someday {
+        (
             Style::default()
-                .bg(theme.surface0)
-                .fg(theme.secondary)
-                .add_modifier(Modifier::BOLD),
+                .fg(theme.subtext)
+                .add_modifier(Modifier::ITALIC),
+            &symbols.bullet,
         )
-        .highlight_symbol(" ➤ ");
+    } else {
+        (Style::default().fg(theme.text), &symbols.bullet)
+    };
+    let (priority_symbol, priority_color) = match task.priority {
+        Priority::High => (&symbols.priority_high, theme.red),
+        Priority::Medium => (&symbols.priority_medium, theme.yellow),
+        Priority::Low => (&symbols.priority_low, theme.green),
+    };
+    let match_style = Style::default()
+        .fg(theme.yellow)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
 
-    f.render_stateful_widget(list, area, &mut app.state);
+    let mut spans = Vec::new();
+    if app.show_line_numbers {
+        spans.push(match number {
+            Some(number) => Span::styled(
+                format!("{:>width$}", number, width = context.number_width),
+                Style::default().fg(theme.subtext),
+            ),
+            None => Span::raw(" ".repeat(context.number_width)),
+        });
+    }
+    if depth > 0 {
+        spans.push(Span::raw(format!(
+            "{}{} ",
+            "  ".repeat(depth),
+            symbols.subtask
+        )));
+    }
+    spans.push(Span::styled(
+        format!(" {} ", symbol),
+        Style::default().fg(theme.primary),
+    ));
+    if task.pinned {
+        spans.push(Span::raw(format!("{} ", symbols.pinned)));
+    }
+    if blocked {
+        spans.push(Span::raw(format!("{} ", symbols.blocked)));
+    }
+    if timing {
+        spans.push(Span::raw(format!("{} ", symbols.timer)));
+    }
+    spans.extend(description_spans(
+        &task.description,
+        description_style(task),
+        &context.search_terms,
+        match_style,
+        Style::default()
+            .fg(theme.blue)
+            .add_modifier(Modifier::UNDERLINED),
+    ));
+    if task.notes.is_some() {
+        spans.push(Span::raw(format!(" {}", symbols.notes)));
+    }
+    if task.recurrence.is_some() {
+        spans.push(Span::styled(
+            format!(" {}", symbols.recurring),
+            Style::default().fg(theme.mauve),
+        ));
+    }
+    if someday {
+        spans.push(Span::styled(
+            format!(" {}", symbols.someday),
+            Style::default().fg(theme.subtext),
+        ));
+    }
+    if task.collapsed && !task.sub_tasks.is_empty() {
+        spans.push(Span::styled(
+            format!(" {}{}", symbols.collapsed, task.sub_tasks.len()),
+            Style::default().fg(theme.subtext),
+        ));
+    }
+    // Without colors, high priority is bold and low is dimmed besides the symbol
+    let priority_style = match task.priority {
+        Priority::High if theme.is_monochrome() => Style::default().add_modifier(Modifier::BOLD),
+        Priority::Low if theme.is_monochrome() => Style::default().add_modifier(Modifier::DIM),
+        _ => Style::default().fg(priority_color),
+    };
+    let priority_span = Span::styled(format!(" {}", priority_symbol), priority_style);
+    let column = app.settings.due_column;
+    if !column {
+        spans.push(priority_span.clone());
+    }
+
+    if let Some(effort) = task.effort {
+        spans.push(Span::styled(
+            format!(" [{}]", effort.label()),
+            Style::default().fg(theme.lavender),
+        ));
+    }
+
+    if let Some(label) = time_label(task) {
+        let over = task
+            .estimate
+            .is_some_and(|estimate| task.time_spent > estimate);
+        spans.push(Span::styled(
+            format!(" [{}]", label),
+            Style::default().fg(if over { theme.red } else { theme.subtext }),
+        ));
+    }
+
+    // In column mode the priority and due date are right-aligned at the edge,
+    // the date padded to the widest one so the dates line up
+    let mut right = Vec::new();
+    match &task.due_date {
+        Some(due_date) if column => {
+            right.push(priority_span);
+            right.push(Span::styled(
+                format!(
+                    " {:>width$}",
+                    format_due_date(due_date, app.settings.time_format),
+                    width = context.due_width
+                ),
+                due_date_style(app, task, due_date, context.now),
+            ));
+        }
+        Some(due_date) => spans.push(Span::styled(
+            format!(
+                " (due: {})",
+                format_due_date(due_date, app.settings.time_format)
+            ),
+            due_date_style(app, task, due_date, context.now),
+        )),
+        None if column => {
+            right.push(priority_span);
+            if context.due_width > 0 {
+                right.push(Span::raw(" ".repeat(context.due_width + 1)));
+            }
+        }
+        None => {}
+    }
+    let right_width = Line::from(right.clone()).width();
+
+    if !task.tags.is_empty() {
+        let used = Line::from(spans.clone()).width() + 1;
+        let shown = if selected {
+            task.tags.len()
+        } else {
+            tags_that_fit(
+                &task.tags,
+                used,
+                context.width.saturating_sub(right_width),
+                app.settings.max_tags,
+            )
+        };
+        spans.push(Span::raw(" "));
+        for tag in task.tags.iter().take(shown) {
+            spans.push(Span::styled(tag.clone(), Style::default().fg(theme.accent)));
+            spans.push(Span::raw(" "));
+        }
+        if shown < task.tags.len() {
+            spans.push(Span::styled(
+                format!("+{} more", task.tags.len() - shown),
+                Style::default().fg(theme.subtext),
+            ));
+        }
+    }
+
+    if !right.is_empty() {
+        let used = Line::from(spans.clone()).width();
+        let padding = context.width.saturating_sub(used + right_width).max(1);
+        // Keep a completed task's strikethrough off the gap
+        spans.push(Span::styled(
+            " ".repeat(padding),
+            Style::default().remove_modifier(Modifier::CROSSED_OUT),
+        ));
+        spans.extend(right);
+    }
+
+    ListItem::new(Line::from(spans)).style(style)
+}
+
+/// How many tags to draw after `used` columns of a row `width` wide. All of
+/// them when they fit; otherwise at most `max_tags`, fewer if those would
+/// still crowd out the "+K more" indicator. The selected row and the detail
+/// pane always show every tag.
+fn tags_that_fit(tags: &[String], used: usize, width: usize, max_tags: usize) -> usize {
+    let widths: Vec<usize> = tags
+        .iter()
+        .map(|tag| Span::raw(tag.as_str()).width() + 1)
+        .collect();
+    if used + widths.iter().sum::<usize>() <= width {
+        return tags.len();
+    }
+    let mut end = used;
+    let mut shown = 0;
+    for tag_width in widths.iter().take(max_tags) {
+        let more = format!("+{} more", tags.len() - shown - 1).len();
+        if end + tag_width + more > width {
+            break;
+        }
+        end += tag_width;
+        shown += 1;
+    }
+    shown
+}
+
+/// Style for completed tasks, from the `completed_style` setting.
+fn completed_style(app: &App) -> Style {
+    let settings = &app.settings.completed_style;
+    let color = match &settings.color {
+        Some(color) => color.clone().into(),
+        None => app.theme_manager.get_current_theme().surface2,
+    };
+    let mut style = Style::default().fg(color);
+    if settings.strikethrough {
+        style = style.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if settings.dim {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    style
+}
+
+/// Style of the highlighted row in lists. Without colors the background
+/// can't mark it, so monochrome themes use reverse video.
+fn selection_style(theme: &Theme) -> Style {
+    let style = Style::default()
+        .bg(theme.surface0)
+        .add_modifier(Modifier::BOLD);
+    if theme.is_monochrome() {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    }
+}
+
+/// Time spent against the estimate, e.g. "25m/1h 00m", or just whichever of
+/// the two is known.
+fn time_label(task: &Task) -> Option<String> {
+    let spent = (!task.time_spent.is_zero()).then(|| format_duration(task.time_spent));
+    match (spent, task.estimate.map(format_duration)) {
+        (Some(spent), Some(estimate)) => Some(format!("{}/{}", spent, estimate)),
+        (Some(spent), None) => Some(spent),
+        (None, Some(estimate)) => Some(format!("est {}", estimate)),
+        (None, None) => None,
+    }
+}
+
+/// Where a due date stands under the `due_soon_hours` and `someday_days`
+/// settings.
+fn task_due_state(app: &App, due_date: &str, now: NaiveDateTime) -> Option<DueState> {
+    let soon = chrono::Duration::hours(app.settings.due_soon_hours as i64);
+    let someday = app
+        .settings
+        .someday_days
+        .map(|days| chrono::Duration::days(days as i64));
+    due_state(due_date, now, soon, someday)
+}
+
+/// Red when overdue, bold yellow when due soon, muted otherwise and italic
+/// past the someday horizon. Completed tasks are never styled as urgent.
+fn due_date_style(app: &App, task: &Task, due_date: &str, now: NaiveDateTime) -> Style {
+    let theme = app.theme_manager.get_current_theme();
+    let state = task_due_state(app, due_date, now);
+    if app.settings.due_gradient && !task.is_done() && state != Some(DueState::Someday) {
+        if let Some(color) =
+            due_deadline(due_date).and_then(|deadline| due_gradient_color(theme, deadline - now))
+        {
+            return Style::default().fg(color);
+        }
+    }
+    match state {
+        _ if task.is_done() => Style::default().fg(theme.subtext),
+        Some(DueState::Overdue) if theme.is_monochrome() => {
+            Style::default().add_modifier(Modifier::REVERSED)
+        }
+        Some(DueState::Overdue) => Style::default().fg(theme.red),
+        Some(DueState::Soon) => Style::default()
+            .fg(theme.yellow)
+            .add_modifier(Modifier::BOLD),
+        Some(DueState::Someday) => Style::default()
+            .fg(theme.subtext)
+            .add_modifier(Modifier::ITALIC),
+        Some(DueState::Upcoming) | None => Style::default().fg(theme.subtext),
+    }
+}
+
+/// A due date's color by how long is left: the theme's red when overdue,
+/// fading through orange a day out and yellow a week out to green from a
+/// month on. None when the theme's colors aren't RGB and can't be blended.
+fn due_gradient_color(theme: &Theme, until: chrono::Duration) -> Option<Color> {
+    let orange = lerp_color(theme.red, theme.yellow, 0.5)?;
+    let anchors = [
+        (0.0, theme.red),
+        (1.0, orange),
+        (7.0, theme.yellow),
+        (30.0, theme.green),
+    ];
+    let days = until.num_minutes() as f64 / (24.0 * 60.0);
+    if days <= 0.0 {
+        return Some(theme.red);
+    }
+    for pair in anchors.windows(2) {
+        let ((from_day, from), (to_day, to)) = (pair[0], pair[1]);
+        if days <= to_day {
+            return lerp_color(from, to, (days - from_day) / (to_day - from_day));
+        }
+    }
+    Some(theme.green)
+}
+
+/// The RGB color `t` of the way from `from` to `to`.
+fn lerp_color(from: Color, to: Color, t: f64) -> Option<Color> {
+    let (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) = (from, to) else {
+        return None;
+    };
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Some(Color::Rgb(
+        channel(r1, r2),
+        channel(g1, g2),
+        channel(b1, b2),
+    ))
+}
+
+/// Formats a stored due date for display. Only timed dates are affected by
+/// the time format; date-only and unparseable values are shown as stored.
+fn format_due_date(due_date: &str, time_format: TimeFormat) -> String {
+    match (
+        time_format,
+        NaiveDateTime::parse_from_str(due_date, DUE_DATETIME_FORMAT),
+    ) {
+        (TimeFormat::TwelveHour, Ok(datetime)) => datetime.format("%Y-%m-%d %-I:%M %p").to_string(),
+        _ => due_date.to_string(),
+    }
+}
+
+fn status_symbol<'a>(app: &'a App, task: &Task) -> &'a str {
+    let symbols = &app.settings.symbols;
+    match task.status {
+        Status::Todo => &symbols.bullet,
+        Status::InProgress => &symbols.in_progress,
+        Status::Done => &symbols.check,
+    }
+}
+
+/// Style for a task's description: its color override, unless completed.
+fn description_style(task: &Task) -> Style {
+    match &task.color {
+        Some(color) if !task.is_done() => Style::default().fg(color.clone().into()),
+        _ => Style::default(),
+    }
+}
+
+/// Splits `text` into `base`-styled spans, additionally styling every
+/// case-insensitive occurrence of any of the search `terms` with `highlight`
+/// while keeping the original casing. Web links get `link` on top.
+fn description_spans(
+    text: &str,
+    base: Style,
+    terms: &[String],
+    highlight: Style,
+    link: Style,
+) -> Vec<Span<'static>> {
+    let urls = find_urls(text);
+    if terms.is_empty() && urls.is_empty() {
+        return vec![Span::styled(text.to_string(), base)];
+    }
+
+    // Lowercase char by char, remembering which original char each folded
+    // char came from, so matches map back onto valid boundaries of the text
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut folded = Vec::new();
+    let mut origin = Vec::new();
+    for (index, (_, c)) in chars.iter().enumerate() {
+        for lower in c.to_lowercase() {
+            folded.push(lower);
+            origin.push(index);
+        }
+    }
+
+    // Mark every original char covered by an occurrence of any term
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > folded.len() {
+            continue;
+        }
+        for i in 0..=folded.len() - term.len() {
+            if folded[i..i + term.len()] == term[..] {
+                for &index in &origin[i..i + term.len()] {
+                    marked[index] = true;
+                }
+            }
+        }
+    }
+
+    let linked: Vec<bool> = chars
+        .iter()
+        .map(|(offset, _)| urls.iter().any(|url| url.contains(offset)))
+        .collect();
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for index in 1..=chars.len() {
+        if index == chars.len() || marked[index] != marked[start] || linked[index] != linked[start]
+        {
+            let from = chars[start].0;
+            let to = chars.get(index).map_or(text.len(), |(idx, _)| *idx);
+            let mut style = base;
+            if linked[start] {
+                style = style.patch(link);
+            }
+            if marked[start] {
+                style = style.patch(highlight);
+            }
+            spans.push(Span::styled(text[from..to].to_string(), style));
+            start = index;
+        }
+    }
+    spans
+}
+
+/// One-line gauge of how much of the list is done.
+fn render_progress(f: &mut Frame, app: &App, area: Rect, done: usize, total: usize) {
+    let theme = app.theme_manager.get_current_theme();
+    let ascii = app.settings.ascii == Some(true);
+    let ratio = done as f64 / total as f64;
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(theme.green).bg(theme.surface0))
+        .ratio(ratio)
+        .use_unicode(!ascii)
+        .label(format!("{}/{} done ({:.0}%)", done, total, ratio * 100.0));
+    f.render_widget(gauge, area);
+
+    // Gauge always fills with full blocks; match the ASCII bar chart instead
+    if ascii {
+        let buffer = f.buffer_mut();
+        for x in area.left()..area.right() {
+            let cell = buffer.get_mut(x, area.top());
+            if cell.symbol() == symbols::block::FULL {
+                cell.set_symbol("#");
+            }
+        }
+    }
 }
 
 fn render_footer(f: &mut Frame, app: &App, area: Rect) {
@@ -230,45 +985,232 @@ fn render_footer(f: &mut Frame, app: &App, area: Rect) {
         };
     }
 
-    let help_spans = Line::from(
-        key!("q", ":quit ")
-            .into_iter()
-            .chain(key!("h", ":help "))
-            .chain(key!("a", ":add "))
-            .chain(key!("d", ":delete "))
-            .chain(key!("/", ":search "))
-            .chain(key!("f", ":focus "))
-            .chain(key!("t", ":theme "))
-            .chain(key!("+", ":zoom "))
-            .collect::<Vec<_>>(),
+    if let Some(error) = &app.save_error {
+        let mut error_spans = vec![Span::styled(error.clone(), Style::default().fg(theme.red))];
+        if app.quit_save_failed {
+            error_spans.push(Span::styled(
+                " (press q again to quit without saving)",
+                desc_style,
+            ));
+        }
+        let error_line = Line::from(error_spans);
+        let error_block = Paragraph::new(error_line).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.red))
+                .title(" Error ")
+                .title_style(Style::default().fg(theme.red)),
+        );
+        f.render_widget(error_block, area);
+        return;
+    }
+
+    let mut spans = Vec::new();
+    if let Some((task, elapsed)) = app.timer_status() {
+        let seconds = elapsed.as_secs();
+        spans.push(Span::styled(
+            format!(
+                "{} {} {}:{:02}:{:02} ",
+                app.settings.symbols.timer,
+                task.description,
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+            Style::default().fg(theme.yellow),
+        ));
+    }
+    spans.extend(
+        footer_hints(app)
+            .iter()
+            .flat_map(|(key, desc)| key!(*key, format!(":{} ", desc))),
     );
+    let help_spans = Line::from(spans);
 
+    // The startup summary takes the title's place until dismissed
+    let (title, title_color) = match &app.due_summary {
+        Some(summary) => (format!(" {} (Esc: dismiss) ", summary), theme.yellow),
+        None => (" Controls ".to_string(), theme.secondary),
+    };
     let help = Paragraph::new(help_spans).block(
         Block::default()
             .borders(Borders::ALL)
+            .border_set(border_set(app))
             .border_style(Style::default().fg(theme.surface1))
-            .title(" Controls ")
-            .title_style(Style::default().fg(theme.secondary)),
+            .title(title)
+            .title_style(Style::default().fg(title_color)),
     );
 
     f.render_widget(help, area);
 }
 
+/// The keys that do something in the current mode, as (key, description).
+fn footer_hints(app: &App) -> &'static [(&'static str, &'static str)] {
+    if app.leader_keys.is_some() {
+        return &[("key", "run"), ("Esc", "cancel")];
+    }
+    match app.mode {
+        AppMode::Normal => &[
+            ("q", "quit"),
+            ("h", "help"),
+            ("a", "add"),
+            ("d", "delete"),
+            ("/", "search"),
+            ("f", "focus"),
+            ("t", "theme"),
+            ("+", "zoom"),
+            (":", "commands"),
+        ],
+        AppMode::Insert if !app.tag_suggestions().is_empty() => &[
+            ("Tab", "complete tag"),
+            ("↑/↓", "select"),
+            ("Esc", "dismiss"),
+        ],
+        AppMode::Insert => &[
+            ("Enter", "save"),
+            ("Ctrl+Enter", "save & add another"),
+            ("Tab", "keep open"),
+            ("Esc", "cancel"),
+        ],
+        AppMode::DateInput => &[("Enter", "set"), ("Esc", "cancel")],
+        AppMode::EstimateInput => &[("Enter", "set"), ("Esc", "cancel")],
+        AppMode::EffortInput => &[("Enter", "set"), ("Esc", "cancel")],
+        AppMode::RecurrenceInput => &[("Enter", "set"), ("Esc", "cancel")],
+        AppMode::TagInput => &[("Enter", "add"), ("Esc", "cancel")],
+        AppMode::MoveTo => &[("0-9", "position"), ("Enter", "move"), ("Esc", "cancel")],
+        AppMode::Search => &[
+            ("Enter", "apply"),
+            ("Esc", "cancel"),
+            ("↑/↓", "history"),
+            ("Tab", "scope"),
+        ],
+        AppMode::Notes => &[("Enter", "new line"), ("Esc", "save")],
+        AppMode::CommandPalette => &[("Enter", "run"), ("↑/↓", "select"), ("Esc", "close")],
+        AppMode::ColorPicker => &[("↑/↓", "select"), ("Enter", "apply"), ("Esc", "cancel")],
+        AppMode::DependencyPicker => &[("↑/↓", "select"), ("Enter", "toggle"), ("Esc", "done")],
+        AppMode::PresetPicker if app.preset_name_input.is_some() => {
+            &[("Enter", "save"), ("Esc", "cancel")]
+        }
+        AppMode::PresetPicker => &[
+            ("↑/↓", "select"),
+            ("Enter", "apply"),
+            ("s", "save current"),
+            ("d", "delete"),
+            ("Esc", "close"),
+        ],
+        AppMode::ThemeEditor if app.theme_color_input.is_some() => {
+            &[("Enter", "set"), ("Esc", "cancel")]
+        }
+        AppMode::ThemeEditor => &[
+            ("↑/↓", "select"),
+            ("Enter", "edit"),
+            ("s", "save"),
+            ("Esc", "discard"),
+        ],
+        AppMode::Confirm
+            if matches!(
+                app.confirm_dialog.as_ref().map(|dialog| &dialog.action),
+                Some(ConfirmAction::DeleteWithSubtasks(_))
+            ) =>
+        {
+            &[("y", "delete all"), ("p", "keep subtasks"), ("n", "no")]
+        }
+        AppMode::Confirm => &[("y", "yes"), ("n", "no")],
+        AppMode::Stats => &[("Tab", "range"), ("Esc", "close")],
+        AppMode::Help => &[("↑/↓", "scroll"), ("PgUp/PgDn", "page"), ("Esc", "close")],
+    }
+}
+
 fn render_input_popup(f: &mut Frame, app: &App) {
     let theme = app.theme_manager.get_current_theme();
     let area = centered_rect(60, 20, f.size());
 
-    let title = if app.adding_subtask {
-        " New Subtask "
-    } else {
-        " New Task "
+    let title = match (app.adding_subtask, app.sticky_add) {
+        (true, false) => " New Subtask ",
+        (false, false) => " New Task ",
+        (true, true) => " New Subtask (stays open, Tab: close after adding) ",
+        (false, true) => " New Task (stays open, Tab: close after adding) ",
     };
 
+    let mut block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(theme.primary))
+        .title_style(Style::default().fg(theme.secondary));
+    if let Some(max) = app.settings.max_input_length {
+        let length = app.input.chars().count();
+        let length_style = if length >= max {
+            Style::default().fg(theme.yellow)
+        } else {
+            Style::default().fg(theme.subtext)
+        };
+        block = block.title(
+            Title::from(Span::styled(format!(" {}/{} ", length, max), length_style))
+                .alignment(Alignment::Right)
+                .position(Position::Bottom),
+        );
+    }
+
     let input_block = Paragraph::new(app.input.as_str())
+        .block(block)
+        .style(Style::default().fg(theme.text));
+
+    f.render_widget(Clear, area);
+    f.render_widget(input_block, area);
+    render_tag_suggestions(f, app, area);
+}
+
+/// Existing tags completing the `#` word being typed, in a dropdown hanging
+/// from the bottom of the add popup.
+fn render_tag_suggestions(f: &mut Frame, app: &App, popup: Rect) {
+    let suggestions = app.tag_suggestions();
+    if suggestions.is_empty() {
+        return;
+    }
+    let theme = app.theme_manager.get_current_theme();
+    let size = f.size();
+    let height = (suggestions.len() as u16 + 2).min(size.bottom().saturating_sub(popup.bottom()));
+    if height < 3 {
+        return;
+    }
+    let area = Rect::new(popup.x + 1, popup.bottom(), popup.width.min(30), height);
+
+    let items: Vec<ListItem> = suggestions
+        .into_iter()
+        .map(|tag| ListItem::new(Span::styled(tag, Style::default().fg(theme.accent))))
+        .collect();
+    let mut state = ListState::default();
+    state.select(app.tag_suggestion_index);
+    let list = List::new(items)
         .block(
             Block::default()
-                .title(title)
+                .title(" Tab: complete ")
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.surface1))
+                .title_style(Style::default().fg(theme.subtext)),
+        )
+        .highlight_style(selection_style(theme));
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_date_input_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(60, 20, f.size());
+    let input_block = Paragraph::new(app.date_input.as_str())
+        .block(
+            Block::default()
+                .title(match (app.batch_due_date, app.batch_subtasks) {
+                    (true, true) => " Set Due Date For All Filtered Tasks And Subtasks ",
+                    (true, false) => " Set Due Date For All Filtered Tasks ",
+                    (false, _) => " Set Due Date ",
+                })
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(theme.primary))
                 .title_style(Style::default().fg(theme.secondary)),
         )
@@ -278,14 +1220,55 @@ fn render_input_popup(f: &mut Frame, app: &App) {
     f.render_widget(input_block, area);
 }
 
-fn render_date_input_popup(f: &mut Frame, app: &App) {
+fn render_tag_input_popup(f: &mut Frame, app: &App) {
     let theme = app.theme_manager.get_current_theme();
     let area = centered_rect(60, 20, f.size());
-    let input_block = Paragraph::new(app.date_input.as_str())
+    let input_block = Paragraph::new(app.tag_input.as_str())
+        .block(
+            Block::default()
+                .title(if app.batch_subtasks {
+                    " Tag All Filtered Tasks And Subtasks (e.g. #sprint review) "
+                } else {
+                    " Tag All Filtered Tasks (e.g. #sprint review) "
+                })
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .style(Style::default().fg(theme.text));
+
+    f.render_widget(Clear, area);
+    f.render_widget(input_block, area);
+}
+
+fn render_estimate_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(40, 20, f.size());
+    let input_block = Paragraph::new(app.estimate_input.as_str())
+        .block(
+            Block::default()
+                .title(" Time Estimate (e.g. 1h30m, 45m) ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .style(Style::default().fg(theme.text));
+
+    f.render_widget(Clear, area);
+    f.render_widget(input_block, area);
+}
+
+fn render_effort_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(40, 20, f.size());
+    let input_block = Paragraph::new(app.effort_input.as_str())
         .block(
             Block::default()
-                .title(" Set Due Date ")
+                .title(" Effort (S, M, L or points) ")
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(theme.primary))
                 .title_style(Style::default().fg(theme.secondary)),
         )
@@ -295,17 +1278,34 @@ fn render_date_input_popup(f: &mut Frame, app: &App) {
     f.render_widget(input_block, area);
 }
 
-fn render_search_popup(f: &mut Frame, app: &App) {
+fn render_recurrence_popup(f: &mut Frame, app: &App) {
     let theme = app.theme_manager.get_current_theme();
-    let area = centered_rect(80, 20, f.size());
-    let search_help = "Search by: description, tags, priority (high/medium/low), status (completed/incomplete), due date";
-    let input_text = format!("{}\n\n{}", app.search_input, search_help);
+    let area = centered_rect(40, 20, f.size());
+    let input_block = Paragraph::new(app.recurrence_input.as_str())
+        .block(
+            Block::default()
+                .title(" Repeat (daily, weekly, monthly, yearly or 3d) ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .style(Style::default().fg(theme.text));
 
-    let input_block = Paragraph::new(input_text)
+    f.render_widget(Clear, area);
+    f.render_widget(input_block, area);
+}
+
+fn render_move_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(40, 20, f.size());
+    let task_count = app.get_displayed_tasks().len();
+    let input_block = Paragraph::new(app.move_input.as_str())
         .block(
             Block::default()
-                .title(" Search Tasks ")
+                .title(format!(" Move To Position (1-{}) ", task_count))
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(theme.primary))
                 .title_style(Style::default().fg(theme.secondary)),
         )
@@ -315,18 +1315,403 @@ fn render_search_popup(f: &mut Frame, app: &App) {
     f.render_widget(input_block, area);
 }
 
+/// Which-key style hint listing the keys that can follow the pending leader
+/// sequence, in the bottom-right corner above the footer.
+fn render_leader_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let keys = app.leader_keys.as_deref().unwrap_or_default();
+    let Some(entries) = leader_entries(app, keys) else {
+        return;
+    };
+
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(key, node)| {
+            let (label, style) = match node {
+                LeaderNode::Group(label, _) => {
+                    (format!("+{}", label), Style::default().fg(theme.accent))
+                }
+                LeaderNode::Run(action) => (action.label(), Style::default().fg(theme.text)),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(" {} ", key),
+                    Style::default()
+                        .fg(theme.yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(label, style),
+            ])
+        })
+        .collect();
+
+    let screen = f.size();
+    let width = 36.min(screen.width);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect {
+        x: screen.width.saturating_sub(width + 2),
+        y: screen.height.saturating_sub(height + 4),
+        width,
+        height,
+    };
+    let sequence: String = std::iter::once("Space".to_string())
+        .chain(keys.iter().map(|k| k.to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(format!(" {} ", sequence))
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        ),
+        area,
+    );
+}
+
+fn render_command_palette(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(60, 60, f.size());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Command Palette (Enter: run, Esc: close) ")
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(theme.primary))
+        .title_style(Style::default().fg(theme.secondary));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+        .split(inner);
+    f.render_widget(
+        Paragraph::new(format!(": {}", app.palette_input)).style(Style::default().fg(theme.text)),
+        chunks[0],
+    );
+
+    let items: Vec<ListItem> = app
+        .palette_matches()
+        .iter()
+        .map(|action| {
+            let mut spans = vec![Span::styled(
+                action.label(),
+                Style::default().fg(theme.text),
+            )];
+            if let Some(key) = action.key_hint() {
+                spans.push(Span::styled(
+                    format!("  {}", key),
+                    Style::default().fg(theme.subtext),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let mut state = ListState::default();
+    if !items.is_empty() {
+        state.select(Some(app.palette_index));
+    }
+    let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+    let list = List::new(items)
+        .highlight_style(selection_style(theme))
+        .highlight_symbol(&highlight_symbol);
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+/// The one-line search input drawn above the list while searching. The
+/// list below is filtered by it as it is typed.
+fn render_search_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme_manager.get_current_theme();
+    let search_help = " #tags, high/low, done/wip, dates, \"phrases\" ";
+    let title = match app
+        .search_scope
+        .and_then(|id| app.tasks.iter().find(|t| t.id == id))
+    {
+        Some(task) => format!(" Search in '{}' ", task.description),
+        None => " Search Tasks ".to_string(),
+    };
+    // The hint gives way to the title when the bar is too narrow for both
+    let search_help =
+        if Span::raw(title.as_str()).width() + search_help.len() + 4 <= area.width as usize {
+            search_help
+        } else {
+            ""
+        };
+
+    let input_block = Paragraph::new(Line::from(vec![
+        Span::styled("/ ", Style::default().fg(theme.subtext)),
+        Span::styled(app.search_input.clone(), Style::default().fg(theme.text)),
+    ]))
+    .block(
+        Block::default()
+            .title(title)
+            .title(
+                Title::from(Span::styled(
+                    search_help,
+                    Style::default().fg(theme.subtext),
+                ))
+                .alignment(Alignment::Right),
+            )
+            .borders(Borders::ALL)
+            .border_set(border_set(app))
+            .border_style(Style::default().fg(theme.primary))
+            .title_style(Style::default().fg(theme.secondary)),
+    );
+
+    f.render_widget(input_block, area);
+}
+
+fn render_notes_popup(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(70, 50, f.size());
+    let input_block = Paragraph::new(app.notes_input.as_str())
+        .block(
+            Block::default()
+                .title(" Notes (Enter: new line, Esc: save) ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .style(Style::default().fg(theme.text))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(input_block, area);
+}
+
+fn render_color_picker(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(40, 50, f.size());
+
+    let mut items = vec![ListItem::new(Line::from(Span::styled(
+        " None (default)",
+        Style::default().fg(theme.text),
+    )))];
+    for (name, color) in theme.palette() {
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(" ■ ", Style::default().fg(color)),
+            Span::styled(name, Style::default().fg(color)),
+        ])));
+    }
+
+    let mut state = ListState::default();
+    state.select(Some(app.color_picker_index));
+    let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(" Task Color (Enter: apply, Esc: cancel) ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .highlight_style(selection_style(theme))
+        .highlight_symbol(&highlight_symbol);
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// The theme's color slots with a swatch and hex value each. Pressing Enter
+/// opens an input for the highlighted slot at the bottom of the popup.
+fn render_theme_editor(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(40, 70, f.size());
+
+    let items: Vec<ListItem> = COLOR_FIELDS
+        .iter()
+        .zip(theme.colors())
+        .map(|(field, color)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(" ■ ", Style::default().fg(color)),
+                Span::styled(format!("{:<12}", field), Style::default().fg(theme.text)),
+                Span::styled(color_hex(color), Style::default().fg(theme.subtext)),
+            ]))
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(format!(" Edit Theme: {} ", theme.name))
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(theme.primary))
+        .title_style(Style::default().fg(theme.secondary));
+    let inner = block.inner(area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let mut state = ListState::default();
+    state.select(Some(app.theme_editor_index));
+    let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+    let list = List::new(items)
+        .highlight_style(selection_style(theme))
+        .highlight_symbol(&highlight_symbol);
+    let input = match &app.theme_color_input {
+        Some(input) => Line::from(vec![
+            Span::styled(" New color: ", Style::default().fg(theme.secondary)),
+            Span::styled(input.clone(), Style::default().fg(theme.text)),
+        ]),
+        None => Line::from(Span::styled(
+            " #rrggbb or r,g,b",
+            Style::default().fg(theme.subtext),
+        )),
+    };
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    f.render_stateful_widget(list, chunks[0], &mut state);
+    f.render_widget(Paragraph::new(input), chunks[1]);
+}
+
+/// Saved filter presets with what each one filters by. Saving the current
+/// filters asks for a name at the bottom of the popup.
+fn render_preset_picker(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(50, 50, f.size());
+
+    let items: Vec<ListItem> = app
+        .filter_presets
+        .iter()
+        .map(|preset| {
+            let mut parts = Vec::new();
+            if !preset.query.is_empty() {
+                parts.push(preset.query.clone());
+            }
+            if preset.hide_completed {
+                parts.push("hide completed".to_string());
+            }
+            if preset.focus {
+                parts.push("focus".to_string());
+            }
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{}  ", preset.name),
+                    Style::default().fg(theme.text),
+                ),
+                Span::styled(parts.join(", "), Style::default().fg(theme.subtext)),
+            ]))
+        })
+        .collect();
+
+    let block = Block::default()
+        .title(" Filter Presets ")
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(theme.primary))
+        .title_style(Style::default().fg(theme.secondary));
+    let inner = block.inner(area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let input = match &app.preset_name_input {
+        Some(input) => Line::from(vec![
+            Span::styled(" Name: ", Style::default().fg(theme.secondary)),
+            Span::styled(input.clone(), Style::default().fg(theme.text)),
+        ]),
+        None => Line::from(Span::styled(
+            " s: save the current filters",
+            Style::default().fg(theme.subtext),
+        )),
+    };
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    if items.is_empty() {
+        f.render_widget(
+            Paragraph::new(" No presets yet").style(Style::default().fg(theme.subtext)),
+            chunks[0],
+        );
+    } else {
+        let mut state = ListState::default();
+        state.select(Some(app.preset_picker_index));
+        let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+        let list = List::new(items)
+            .highlight_style(selection_style(theme))
+            .highlight_symbol(&highlight_symbol);
+        f.render_stateful_widget(list, chunks[0], &mut state);
+    }
+    f.render_widget(Paragraph::new(input), chunks[1]);
+}
+
+fn render_dependency_picker(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(60, 60, f.size());
+
+    let blocked_by: &[usize] = app
+        .dependency_target
+        .and_then(|id| app.tasks.iter().find(|t| t.id == id))
+        .map_or(&[], |t| t.blocked_by.as_slice());
+    let items: Vec<ListItem> = app
+        .dependency_candidates()
+        .iter()
+        .map(|candidate| {
+            let checkbox = if blocked_by.contains(&candidate.id) {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            let style = if candidate.is_done() {
+                Style::default().fg(theme.surface2)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(checkbox, Style::default().fg(theme.primary)),
+                Span::styled(candidate.description.clone(), style),
+            ]))
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.dependency_picker_index));
+    let highlight_symbol = format!(" {} ", app.settings.symbols.highlight);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(" Blocked By (Enter: toggle, Esc: done) ")
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(theme.primary))
+                .title_style(Style::default().fg(theme.secondary)),
+        )
+        .highlight_style(selection_style(theme))
+        .highlight_symbol(&highlight_symbol);
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
 fn render_confirm_dialog(f: &mut Frame, app: &App) {
     let theme = app.theme_manager.get_current_theme();
 
     if let Some(dialog) = &app.confirm_dialog {
         let area = centered_rect(60, 30, f.size());
-        let content = format!("{}\n\nPress 'y' to confirm, 'n' to cancel", dialog.message);
+        let keys = match dialog.action {
+            ConfirmAction::DeleteWithSubtasks(_) => {
+                "Press 'y' to delete all, 'p' to keep the subtasks as tasks, 'n' to cancel"
+            }
+            _ => "Press 'y' to confirm, 'n' to cancel",
+        };
+        let content = format!("{}\n\n{}", dialog.message, keys);
 
         let confirm_block = Paragraph::new(content)
             .block(
                 Block::default()
                     .title(" Confirm Action ")
                     .borders(Borders::ALL)
+                    .border_set(border_set(app))
                     .border_style(Style::default().fg(theme.red))
                     .title_style(Style::default().fg(theme.red)),
             )
@@ -337,40 +1722,33 @@ fn render_confirm_dialog(f: &mut Frame, app: &App) {
     }
 }
 
-fn render_help_dialog(f: &mut Frame, app: &App) {
-    let theme = app.theme_manager.get_current_theme();
+/// The key reference. It scrolls with ↑/↓ and PageUp/PageDown when it is
+/// taller than the popup, with the position shown at the bottom.
+fn render_help_dialog(f: &mut Frame, app: &mut App) {
+    let theme = app.theme_manager.get_current_theme().clone();
     let area = centered_rect(80, 80, f.size());
 
     let current_theme_name = &app.theme_manager.get_current_theme().name;
     let available_themes: Vec<String> = app.get_available_theme_names();
 
+    // Generated from the actions themselves so the keys listed can't drift
+    // from the bindings
+    let mut keybindings = String::new();
+    for (heading, actions) in help_sections() {
+        keybindings.push_str(&format!("  {}:\n", heading));
+        for action in actions {
+            if let Some(key) = action.help_key(app) {
+                keybindings.push_str(&format!("    {:<10}- {}\n", key, action.label()));
+            }
+        }
+        keybindings.push('\n');
+    }
+
     let help_content = format!(
         "📝 Advanced Todo List - Help
 
 🔑 KEYBINDINGS:
-  Navigation:
-    ↑/↓      - Move selection up/down
-    Enter    - Toggle task completion
-    
-  Task Management:
-    a        - Add new task
-    s        - Add subtask to selected task
-    d        - Delete selected task (with confirmation)
-    p        - Cycle task priority (High/Medium/Low)
-    D        - Set due date for selected task
-    
-  View & Search:
-    /        - Search/filter tasks
-    f        - Toggle focus mode (hide completed)
-    +/-      - Zoom in/out
-    
-  Themes & Help:
-    t        - Cycle through themes
-    h/F1     - Show/hide this help
-    C        - Clear completed tasks (with confirmation)
-    q        - Quit application
-
-🎨 THEMES:
+{}🎨 THEMES:
   Current: {}
   Available: {}
   
@@ -379,37 +1757,225 @@ fn render_help_dialog(f: &mut Frame, app: &App) {
   • Tag support (#work #urgent)
   • Priority levels with visual indicators
   • Subtasks with nesting
-  • Search by content, tags, priority, or status
+  • Dependencies: {} marks tasks with unfinished blockers
+  • Multi-line notes on any task ({} marks tasks with notes)
+  • Search by content, tags, priority, status, or due date
   • Focus mode to hide completed tasks
   • Multiple color themes
 
+📅 DATES:
+  Ambiguous dates like 1/6 are read as {}
+  (change with --date-format us|uk or settings.json)
+
 💡 TIPS:
   • Use natural language for dates: \"call mom tomorrow at 3pm\"
   • Add tags with #: \"buy milk #shopping #urgent\"
+  • Search for due:today, due:overdue, due:this week or due:2026-06
+  • Size tasks with e (S/M/L or points) and search for effort:large
+  • Make a task repeat with r; completing it adds the next occurrence
+  • The : palette also completes, deletes, tags or reprioritizes every
+    filtered task at once
+  • Ctrl+C quits too; Ctrl+Enter adds a task and keeps the popup open
   • Use focus mode (f) to concentrate on pending tasks
   • Cycle themes (t) to find your preferred style
 
 Press ESC, h, F1, or q to close this help.",
+        keybindings,
         current_theme_name,
-        available_themes.join(", ")
+        available_themes.join(", "),
+        app.settings.symbols.blocked,
+        app.settings.symbols.notes,
+        app.settings.date_dialect.label()
     );
 
+    // Rows the text takes once wrapped, to know how far it can scroll
+    let inner_width = area.width.saturating_sub(2).max(1) as usize;
+    let inner_height = area.height.saturating_sub(2);
+    let content_height: usize = help_content
+        .lines()
+        .map(|line| wrapped_rows(line, inner_width))
+        .sum();
+    let max_scroll = (content_height as u16).saturating_sub(inner_height);
+    app.help_scroll = app.help_scroll.min(max_scroll);
+
+    let mut block = Block::default()
+        .title(" Help ")
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(theme.primary))
+        .title_style(Style::default().fg(theme.secondary));
+    if max_scroll > 0 {
+        let more = match app.help_scroll {
+            0 => "↓",
+            scroll if scroll == max_scroll => "↑",
+            _ => "↑↓",
+        };
+        block = block.title(
+            Title::from(Span::styled(
+                format!(
+                    " {} {}/{} (↑/↓, PgUp/PgDn) ",
+                    more,
+                    app.help_scroll + inner_height.min(content_height as u16),
+                    content_height
+                ),
+                Style::default().fg(theme.subtext),
+            ))
+            .alignment(Alignment::Right)
+            .position(Position::Bottom),
+        );
+    }
     let help_block = Paragraph::new(help_content)
+        .block(block)
+        .style(Style::default().fg(theme.text))
+        .wrap(Wrap { trim: true })
+        .scroll((app.help_scroll, 0));
+
+    f.render_widget(Clear, area);
+    f.render_widget(help_block, area);
+}
+
+/// Rows a line takes when word-wrapped to `width` the way `Wrap { trim:
+/// true }` does: greedily by words, keeping the spaces between words but
+/// dropping them at the start of a row.
+fn wrapped_rows(line: &str, width: usize) -> usize {
+    let mut rows = 1;
+    let mut used = 0;
+    let mut spaces = 0;
+    for word in line.trim_start().split(' ') {
+        if word.is_empty() {
+            spaces += 1;
+            continue;
+        }
+        let word_width = Span::raw(word).width();
+        let gap = std::mem::replace(&mut spaces, 1);
+        if used > 0 && used + gap + word_width <= width {
+            used += gap + word_width;
+            continue;
+        }
+        if used > 0 {
+            rows += 1;
+        }
+        // A word longer than the line is broken across rows
+        rows += word_width.saturating_sub(1) / width;
+        used = (word_width - 1) % width + 1;
+    }
+    rows
+}
+
+fn render_stats_dialog(f: &mut Frame, app: &App) {
+    let theme = app.theme_manager.get_current_theme();
+    let area = centered_rect(80, 60, f.size());
+
+    let data = app.completions_per_day(app.stats_days);
+    let total: u64 = data.iter().map(|(_, count)| count).sum();
+    let bars: Vec<(&str, u64)> = data
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect();
+
+    // Spread the bars over the available width, leaving room for the borders
+    let inner_width = area.width.saturating_sub(2);
+    let bar_gap = 1;
+    let bar_width = (inner_width / app.stats_days as u16)
+        .saturating_sub(bar_gap)
+        .max(1);
+
+    let chart = BarChart::default()
         .block(
             Block::default()
-                .title(" Help ")
+                .title(format!(
+                    " Completed per day — last {} days ({} total, {}{}, Tab: range, Esc: close) ",
+                    app.stats_days,
+                    total,
+                    streak_label(app),
+                    pending_effort_label(app)
+                ))
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(theme.primary))
                 .title_style(Style::default().fg(theme.secondary)),
         )
-        .style(Style::default().fg(theme.text))
-        .wrap(Wrap { trim: true });
+        .data(&bars)
+        .bar_width(bar_width)
+        .bar_gap(bar_gap)
+        .bar_set(bar_set(app))
+        .bar_style(Style::default().fg(theme.green))
+        .value_style(
+            Style::default()
+                .fg(theme.background)
+                .bg(theme.green)
+                .add_modifier(Modifier::BOLD),
+        )
+        .label_style(Style::default().fg(theme.subtext))
+        .style(Style::default().bg(theme.background));
 
     f.render_widget(Clear, area);
-    f.render_widget(help_block, area);
+    f.render_widget(chart, area);
+}
+
+/// Open effort for the stats title, e.g. ", open effort: 13pt, 2S 1L"; empty
+/// when no open task has one.
+fn pending_effort_label(app: &App) -> String {
+    let (points, sizes) = app.pending_effort();
+    let mut parts: Vec<String> = Vec::new();
+    if points > 0 {
+        parts.push(format!("{}pt", points));
+    }
+    let sized: Vec<String> = sizes
+        .iter()
+        .zip(["S", "M", "L"])
+        .filter(|(count, _)| **count > 0)
+        .map(|(count, size)| format!("{}{}", count, size))
+        .collect();
+    if !sized.is_empty() {
+        parts.push(sized.join(" "));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(", open effort: {}", parts.join(", "))
+    }
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
+/// Plain ASCII box for terminals without line-drawing characters.
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+fn border_set(app: &App) -> border::Set {
+    if app.settings.ascii == Some(true) {
+        ASCII_BORDER
+    } else {
+        border::PLAIN
+    }
+}
+
+fn bar_set(app: &App) -> bar::Set {
+    if app.settings.ascii == Some(true) {
+        bar::Set {
+            full: "#",
+            seven_eighths: "#",
+            three_quarters: "#",
+            five_eighths: "#",
+            half: "#",
+            three_eighths: "#",
+            one_quarter: "#",
+            one_eighth: "#",
+            empty: " ",
+        }
+    } else {
+        bar::NINE_LEVELS
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)